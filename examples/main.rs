@@ -44,4 +44,6 @@ fn main() {
             ..Default::default()
         }
     );
+
+    glog::shutdown();
 }
@@ -0,0 +1,139 @@
+//! Router agnostic handler to inspect and change the logging flags over HTTP
+//!
+//! Similar in spirit to the `/flagz` pages of servers using glog. Hook [`handle`] into the
+//! HTTP server of your choice.
+//!
+//! # Example
+//!
+//! ```
+//! use glog::{admin, Flags};
+//!
+//! glog::new().init(Flags {
+//!         logtostderr: true,
+//!         ..Default::default()
+//!     }).unwrap();
+//!
+//! let response = admin::handle("POST", "v=2&minloglevel=WARN");
+//! assert_eq!(response.status, 200);
+//!
+//! let response = admin::handle("GET", "");
+//! assert!(response.body.contains("--v=2\n"));
+//! assert!(response.body.contains("--minloglevel=WARN\n"));
+//!
+//! // nothing is changed by a request with an invalid value
+//! let response = admin::handle("POST", "v=3&minloglevel=LOUD");
+//! assert_eq!(response.status, 400);
+//! assert!(admin::handle("GET", "").body.contains("--v=2\n"));
+//! assert!(admin::handle("HEAD", "").body.is_empty());
+//! ```
+
+use std::str::FromStr;
+
+use log::Level;
+
+use crate::{logger, Flags, LoggerHandle};
+
+/// Response to an admin request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// Value of the `Content-Type` header
+    pub content_type: &'static str,
+    /// Plain text body
+    pub body: String,
+}
+
+impl AdminResponse {
+    fn new(status: u16, body: String) -> Self {
+        AdminResponse {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body,
+        }
+    }
+}
+
+/// Handle an admin request with the HTTP `method` and the URL `query` without the leading `?`
+///
+/// `GET` lists the current flags, one `--name=value` per line, and `HEAD` only answers with the
+/// status and content type. `POST` and `PUT` change the flags given as `name=value` pairs in the
+/// query and list the flags afterwards. Either all changes of a request are applied or none. Only the flags which
+/// [`LoggerHandle`] can change at runtime are accepted: `v`, `minloglevel`, `colorlogtostderr`,
+/// `logtostderr`, `alsologtostderr` and `log_backtrace_at` (empty to disable).
+pub fn handle(method: &str, query: &str) -> AdminResponse {
+    let logger = match logger() {
+        Some(logger) => logger,
+        None => return AdminResponse::new(503, "glog isn't initialized\n".to_owned()),
+    };
+    match method {
+        "GET" => AdminResponse::new(200, describe(&logger.flags())),
+        "HEAD" => AdminResponse::new(200, String::new()),
+        "POST" | "PUT" => match apply(&logger, query) {
+            Ok(()) => AdminResponse::new(200, describe(&logger.flags())),
+            Err(why) => AdminResponse::new(400, format!("{}\n", why)),
+        },
+        _ => AdminResponse::new(405, format!("unsupported method {}\n", method)),
+    }
+}
+
+fn describe(flags: &Flags) -> String {
+    format!(
+        "--v={}\n--minloglevel={}\n--colorlogtostderr={}\n--logtostderr={}\n--alsologtostderr={}\n--log_backtrace_at={}\n--log_dir={}\n",
+        flags.v,
+        flags.minloglevel.as_str(),
+        flags.colorlogtostderr,
+        flags.logtostderr,
+        flags.alsologtostderr,
+        flags.log_backtrace_at.as_deref().unwrap_or(""),
+        flags.log_dir.to_string_lossy(),
+    )
+}
+
+fn apply(logger: &LoggerHandle, query: &str) -> Result<(), String> {
+    let mut flags = logger.flags();
+    // the changes are swapped in at once so a bad request doesn't change some of the flags
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = decode(value)?;
+        match decode(name)?.as_str() {
+            "v" => flags.v = parse(name, &value)?,
+            "minloglevel" => flags.minloglevel = parse::<Level>(name, &value)?,
+            "colorlogtostderr" => flags.colorlogtostderr = parse(name, &value)?,
+            "logtostderr" => flags.logtostderr = parse(name, &value)?,
+            "alsologtostderr" => flags.alsologtostderr = parse(name, &value)?,
+            "log_backtrace_at" => flags.log_backtrace_at = Some(value).filter(|value| !value.is_empty()),
+            other => return Err(format!("flag {} can't be changed at runtime", other)),
+        }
+    }
+    flags.validate().map_err(|why| why.to_string())?;
+    logger.set_flags(flags).map_err(|why| why.to_string())
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for flag {}: {}", name, value))
+}
+
+fn decode(text: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [chars.next(), chars.next()];
+                let decoded = match hex {
+                    [Some(high), Some(low)] => std::str::from_utf8(&[high, low])
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None,
+                };
+                bytes.push(decoded.ok_or_else(|| format!("invalid percent encoding in {}", text))?);
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("invalid UTF-8 in {}", text))
+}
@@ -0,0 +1,276 @@
+//! Sink uploading the log output in chunks to object storage like S3 or GCS
+//!
+//! Meant for hosts without a persistent disk. The upload itself is done by an [`ObjectStore`].
+//! [`CommandStore`] uploads to S3 or GCS through the `aws` or `gcloud` command line tools, which
+//! take care of the credentials and the retries. For any other storage service or to use its
+//! client library instead, implement [`ObjectStore`] based on the client of your choice.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use log::*;
+//! use glog::{archive::{ArchiveSink, ObjectStore}, Flags};
+//!
+//! struct Bucket;
+//!
+//! impl ObjectStore for Bucket {
+//!     fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+//!         // upload with the S3 or GCS client of your choice
+//!         println!("uploading {} bytes to {}", data.len(), key);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! glog::new().init(Flags::default()).unwrap();
+//! glog::add_sink(Box::new(
+//!     ArchiveSink::new(Box::new(Bucket), "logs/my_app").chunk_size(1024 * 1024).retention(100),
+//! ));
+//!
+//! info!("Archived");
+//! glog::flush();
+//! ```
+
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+use chrono::Local;
+
+use crate::{format, Formatter, RecordData, Sink};
+
+/// Default number of bytes collected before a chunk is uploaded
+const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Storage the [`ArchiveSink`] uploads to
+pub trait ObjectStore: Send + Sync {
+    /// Store `data` as the object `key`
+    fn put(&self, key: &str, data: &[u8]) -> io::Result<()>;
+
+    /// Delete the object `key` once it falls out of the retention
+    fn delete(&self, _key: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Uploads and deletes objects by running a command line tool
+///
+/// The commands are given as the program followed by its arguments, `{key}` in the arguments is
+/// replaced by the key of the object. The upload command reads the object from stdin.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{archive::{ArchiveSink, CommandStore}, Flags};
+///
+/// let dir = std::env::temp_dir().join("glog-archive-example");
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// let object = format!("{}/{{key}}", dir.display());
+/// // the same as CommandStore::s3("my-bucket") but into a local directory
+/// let store = CommandStore::new(vec!["sh", "-c", "mkdir -p \"$(dirname \"$0\")\" && cat > \"$0\"", &object])
+///     .delete_with(vec!["rm", &object]);
+///
+/// glog::new().init(Flags::default()).unwrap();
+/// glog::add_sink(Box::new(ArchiveSink::new(Box::new(store), "my_app").retention(1)));
+///
+/// info!("Archived");
+/// glog::flush();
+/// info!("Archived as well");
+/// glog::flush();
+///
+/// let objects = std::fs::read_dir(dir.join("my_app")).unwrap().collect::<Vec<_>>();
+/// assert_eq!(objects.len(), 1);
+/// let object = std::fs::read_to_string(objects[0].as_ref().unwrap().path()).unwrap();
+/// assert!(object.contains("Archived as well"));
+/// ```
+pub struct CommandStore {
+    put: Vec<String>,
+    delete: Option<Vec<String>>,
+}
+
+impl CommandStore {
+    /// Upload with `command`, which reads the object from stdin
+    ///
+    /// Objects are never deleted unless a [delete command](CommandStore::delete_with) is set.
+    pub fn new<I, S>(command: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        CommandStore {
+            put: command.into_iter().map(Into::into).collect(),
+            delete: None,
+        }
+    }
+
+    /// Delete the objects which fall out of the [retention](ArchiveSink::retention) with `command`
+    pub fn delete_with<I, S>(mut self, command: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.delete = Some(command.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Upload to the S3 `bucket` with `aws s3 cp` and delete with `aws s3 rm`
+    pub fn s3(bucket: &str) -> Self {
+        let object = format!("s3://{}/{{key}}", bucket);
+        CommandStore::new(vec!["aws", "s3", "cp", "-", &object]).delete_with(vec!["aws", "s3", "rm", &object])
+    }
+
+    /// Upload to the GCS `bucket` with `gcloud storage cp` and delete with `gcloud storage rm`
+    pub fn gcs(bucket: &str) -> Self {
+        let object = format!("gs://{}/{{key}}", bucket);
+        CommandStore::new(vec!["gcloud", "storage", "cp", "-", &object]).delete_with(vec!["gcloud", "storage", "rm", &object])
+    }
+
+    fn run(command: &[String], key: &str, data: Option<&[u8]>) -> io::Result<()> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+        let mut child = Command::new(program)
+            .args(args.iter().map(|arg| arg.replace("{key}", key)))
+            .stdin(if data.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let (Some(mut stdin), Some(data)) = (child.stdin.take(), data) {
+            stdin.write_all(data)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{} failed with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl ObjectStore for CommandStore {
+    fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        CommandStore::run(&self.put, key, Some(data))
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match &self.delete {
+            Some(command) => CommandStore::run(command, key, None),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Collects messages into chunks and uploads each chunk as an object
+///
+/// Chunks are uploaded once they reach the [`chunk_size`](ArchiveSink::chunk_size) and on every
+/// [`flush`](crate::flush()). The objects are named
+/// `<prefix>/<timestamp>.<pid>.<sequence>.log`. If an upload fails the chunk is kept and uploaded
+/// with the next one.
+pub struct ArchiveSink {
+    store: Box<dyn ObjectStore>,
+    prefix: String,
+    chunk_size: usize,
+    retention: Option<usize>,
+    formatter: Box<Formatter>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    chunk: Vec<u8>,
+    sequence: u64,
+    uploaded: VecDeque<String>,
+}
+
+impl ArchiveSink {
+    /// Upload to `store` below `prefix`
+    pub fn new(store: Box<dyn ObjectStore>, prefix: &str) -> Self {
+        ArchiveSink {
+            store,
+            prefix: prefix.trim_end_matches('/').to_owned(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            retention: None,
+            formatter: Box::new(format::json),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Upload a chunk once it holds `bytes` bytes, 4 MiB by default
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes;
+        self
+    }
+
+    /// Keep only the latest `objects` uploaded by this sink and delete older ones
+    pub fn retention(mut self, objects: usize) -> Self {
+        self.retention = Some(objects);
+        self
+    }
+
+    /// Format each message with `formatter`, JSON lines by default
+    pub fn formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(&RecordData) -> String + Send + Sync + 'static,
+    {
+        self.formatter = Box::new(formatter);
+        self
+    }
+
+    fn upload(&self, state: &mut State) {
+        if state.chunk.is_empty() {
+            return;
+        }
+        let key = if self.prefix.is_empty() {
+            format!(
+                "{}.{}.{}.log",
+                Local::now().format("%Y%m%d-%H%M%S"),
+                std::process::id(),
+                state.sequence
+            )
+        } else {
+            format!(
+                "{}/{}.{}.{}.log",
+                self.prefix,
+                Local::now().format("%Y%m%d-%H%M%S"),
+                std::process::id(),
+                state.sequence
+            )
+        };
+        if self.store.put(&key, &state.chunk).is_err() {
+            return;
+        }
+        state.chunk.clear();
+        state.sequence += 1;
+        state.uploaded.push_back(key);
+        if let Some(retention) = self.retention {
+            while state.uploaded.len() > retention {
+                if let Some(expired) = state.uploaded.pop_front() {
+                    let _ = self.store.delete(&expired);
+                }
+            }
+        }
+    }
+}
+
+impl Sink for ArchiveSink {
+    fn log(&self, record: &RecordData) {
+        let mut state = self.state.lock().unwrap();
+        state.chunk.extend_from_slice((self.formatter)(record).as_bytes());
+        state.chunk.push(b'\n');
+        if state.chunk.len() >= self.chunk_size {
+            self.upload(&mut state);
+        }
+    }
+
+    fn flush(&self) {
+        self.upload(&mut self.state.lock().unwrap());
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use log::{Level, Metadata};
+
+/// Bumped whenever a flag changes which the decisions cached by the callsites depend on
+static GENERATION: AtomicU32 = AtomicU32::new(1);
+
+/// Make all callsites decide again on their next call
+///
+/// Has to be called whenever the flags have been changed.
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Decisions of a single [`vlog!`](crate::vlog) or [`vlog_is_on!`](crate::vlog_is_on) invocation
+///
+/// Whether the target of the callsite passes the filters and the maximum verbosity don't change
+/// between calls unless the flags are changed at runtime, so they are only evaluated once per
+/// generation of the flags.
+#[doc(hidden)]
+pub struct __Callsite {
+    // generation in the upper half, the maximum verbosity logged at the callsite in the lower half
+    state: AtomicU64,
+}
+
+impl __Callsite {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        __Callsite {
+            state: AtomicU64::new(0),
+        }
+    }
+
+    pub fn vlog_is_on(&self, verbosity: i32, target: &str) -> bool {
+        // the maximum level isn't cached as applications may set it through the log crate
+        if Level::Info > log::max_level() {
+            return false;
+        }
+        let generation = GENERATION.load(Ordering::Acquire);
+        let state = self.state.load(Ordering::Relaxed);
+        let max_verbosity = if (state >> 32) as u32 == generation {
+            state as u32 as i32
+        } else {
+            let max_verbosity = match crate::max_verbosity(target) {
+                Some(max_verbosity) => max_verbosity,
+                // without glog installed the logger decides every time, e.g. one of build_boxed
+                // which checks the verbosity itself
+                None => return log::logger().enabled(&Metadata::builder().level(Level::Info).target(target).build()),
+            };
+            self.state.store(
+                u64::from(generation) << 32 | u64::from(max_verbosity as u32),
+                Ordering::Relaxed,
+            );
+            max_verbosity
+        };
+        verbosity <= max_verbosity
+    }
+}
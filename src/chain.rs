@@ -0,0 +1,41 @@
+use std::{error::Error, fmt};
+
+/// Renders an error followed by each of its sources on a line of its own
+///
+/// `Display` implementations of errors usually only describe the error itself, the causes are
+/// reachable through [`Error::source`]. This is what [`log_error!`](crate::log_error) writes.
+///
+/// # Example
+///
+/// ```
+/// use std::io;
+///
+/// let error = io::Error::new(io::ErrorKind::Other, "config missing");
+/// assert_eq!(glog::ErrorChain::new(&error).to_string(), "config missing");
+/// ```
+pub struct ErrorChain<'a, E: ?Sized>(&'a E);
+
+impl<'a, E: Error + ?Sized> ErrorChain<'a, E> {
+    /// Wrap `error` for rendering
+    pub fn new(error: &'a E) -> Self {
+        ErrorChain(error)
+    }
+}
+
+impl<E: Error + ?Sized> fmt::Display for ErrorChain<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        let mut source = self.0.source();
+        while let Some(cause) = source {
+            write!(f, "\n  caused by: {}", cause)?;
+            source = cause.source();
+        }
+        Ok(())
+    }
+}
+
+impl<E: Error + ?Sized> fmt::Debug for ErrorChain<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,68 @@
+use std::{cell::RefCell, marker::PhantomData};
+
+thread_local! {
+    static CONTEXT: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Removes the value pushed with [`push_context`] when dropped
+#[must_use = "the context is removed as soon as the guard is dropped"]
+pub struct ContextGuard {
+    depth: usize,
+    // the context belongs to the thread which pushed it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|context| context.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// Add `value` to the context of all messages logged by the current thread
+///
+/// The values of nested calls are stacked and written in brackets in front of every message, like
+/// `[job-42:parse] message`. The value is removed once the returned guard is dropped.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// let _job = glog::push_context("job-42");
+/// {
+///     let _step = glog::push_context("parse");
+///     info!("Parsing input");
+/// }
+/// info!("Job done");
+/// // I0401 12:34:56.987654   123 doc.rs:11] [job-42:parse] Parsing input
+/// // I0401 12:34:56.987700   123 doc.rs:13] [job-42] Job done
+/// ```
+pub fn push_context<S: Into<String>>(value: S) -> ContextGuard {
+    let depth = CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.push(value.into());
+        context.len() - 1
+    });
+    ContextGuard {
+        depth,
+        _not_send: PhantomData,
+    }
+}
+
+/// The context of the current thread formatted as message prefix
+pub(crate) fn prefix() -> String {
+    CONTEXT.with(|context| {
+        let context = context.borrow();
+        if context.is_empty() {
+            String::new()
+        } else {
+            format!("[{}] ", context.join(":"))
+        }
+    })
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use log::Level;
+
+/// Logs the time elapsed since its creation when dropped
+///
+/// Returned by [`log_duration!`](crate::log_duration).
+#[must_use = "the duration is logged as soon as the guard is dropped"]
+pub struct DurationGuard {
+    level: Level,
+    name: &'static str,
+    threshold: Option<Duration>,
+    target: &'static str,
+    file: &'static str,
+    line: u32,
+    start: Instant,
+}
+
+impl DurationGuard {
+    /// The time elapsed since the guard has been created
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl Drop for DurationGuard {
+    fn drop(&mut self) {
+        let elapsed = self.elapsed();
+        if self.threshold.is_some_and(|threshold| elapsed < threshold) {
+            return;
+        }
+        crate::__log_at(
+            self.level,
+            self.target,
+            self.file,
+            self.line,
+            format_args!("{} took {:?}", self.name, elapsed),
+        );
+    }
+}
+
+#[doc(hidden)]
+pub fn __duration_guard(
+    level: Level,
+    name: &'static str,
+    threshold: Option<Duration>,
+    target: &'static str,
+    file: &'static str,
+    line: u32,
+) -> DurationGuard {
+    DurationGuard {
+        level,
+        name,
+        threshold,
+        target,
+        file,
+        line,
+        start: Instant::now(),
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    io,
+    mem,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use log::Level;
+
+/// Messages kept in a digest at most, later ones are only counted
+const MAX_DIGEST_MESSAGES: usize = 100;
+
+/// Transport used to deliver emails sent by glog
+///
+/// Install one with [`set_email_transport`]. It is used by [`send_email`] and for messages
+/// matching the [`alsologtoemail`](crate::Flags::alsologtoemail) flag.
+///
+/// # Example
+///
+/// ```
+/// use std::io;
+/// use glog::EmailTransport;
+///
+/// struct Printer;
+///
+/// impl EmailTransport for Printer {
+///     fn send(&self, to: &str, subject: &str, body: &str) -> io::Result<()> {
+///         println!("To: {}\nSubject: {}\n\n{}", to, subject, body);
+///         Ok(())
+///     }
+/// }
+///
+/// glog::set_email_transport(Printer);
+///
+/// glog::send_email("oncall@example.com", "Disk full", "Nothing can be written anymore").unwrap();
+/// ```
+pub trait EmailTransport: Send + Sync {
+    /// Deliver an email with `subject` and `body` to the comma separated list of addresses in `to`
+    fn send(&self, to: &str, subject: &str, body: &str) -> io::Result<()>;
+}
+
+static EMAIL_TRANSPORT: RwLock<Option<Arc<dyn EmailTransport>>> = RwLock::new(None);
+
+/// Install the transport used to deliver emails
+pub fn set_email_transport<T: EmailTransport + 'static>(transport: T) {
+    *EMAIL_TRANSPORT.write().unwrap() = Some(Arc::new(transport));
+}
+
+/// Send an email to the comma separated list of addresses in `to`
+///
+/// This is the counterpart to `SendEmail` in glog.
+/// Fails if no transport has been installed with [`set_email_transport`].
+pub fn send_email(to: &str, subject: &str, body: &str) -> io::Result<()> {
+    // don't hold the lock while sending so a transport can't block installing a new one
+    let transport = EMAIL_TRANSPORT.read().unwrap().clone();
+    match transport {
+        Some(transport) => transport.send(to, subject, body),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "no email transport installed")),
+    }
+}
+
+/// Messages collected while emails are throttled
+#[derive(Default)]
+pub(crate) struct EmailDigest {
+    last_sent: Option<Instant>,
+    level: Option<Level>,
+    messages: Vec<String>,
+    omitted: usize,
+}
+
+/// Messages which are due to be sent in a single email
+pub(crate) struct DueDigest {
+    pub(crate) level: Level,
+    pub(crate) messages: Vec<String>,
+    pub(crate) omitted: usize,
+}
+
+impl EmailDigest {
+    /// Add `message` and return the digest if no email has been sent within `interval`
+    pub(crate) fn add(&mut self, interval: Duration, level: Level, message: String) -> Option<DueDigest> {
+        self.level = Some(self.level.map_or(level, |pending| pending.min(level)));
+        if self.messages.len() < MAX_DIGEST_MESSAGES {
+            self.messages.push(message);
+        } else {
+            self.omitted += 1;
+        }
+        self.take_due(interval)
+    }
+
+    /// Return the pending messages if no email has been sent within `interval`
+    pub(crate) fn take_due(&mut self, interval: Duration) -> Option<DueDigest> {
+        if self.last_sent.is_some_and(|last_sent| last_sent.elapsed() < interval) {
+            return None;
+        }
+        let level = self.level.take()?;
+        self.last_sent = Some(Instant::now());
+        Some(DueDigest {
+            level,
+            messages: mem::take(&mut self.messages),
+            omitted: mem::take(&mut self.omitted),
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use std::{error::Error, ffi::OsString, fmt, io};
+
+use log::SetLoggerError;
+
+/// Errors which can occur while initializing glog or writing log messages
+#[derive(Debug)]
+pub enum GlogError {
+    /// A log file, symlink or stderr couldn't be created or written
+    Io {
+        /// Path of the file which caused the error
+        path: OsString,
+        /// The underlying IO error
+        source: io::Error,
+    },
+    /// A flag has a value glog can't work with
+    InvalidFlag {
+        /// Name of the flag
+        flag: &'static str,
+        /// Why the value is invalid
+        reason: String,
+    },
+    /// A command line argument looks like a flag but glog has no flag of this name
+    UnknownFlag {
+        /// Name of the flag without the leading dashes
+        flag: String,
+    },
+    /// Another logger has already been registered with the [`standard logging`] frontend
+    ///
+    /// [`standard logging`]: https://crates.io/crates/log
+    SetLogger(SetLoggerError),
+}
+
+impl GlogError {
+    pub(crate) fn io(path: &OsString, source: io::Error) -> Self {
+        GlogError::Io {
+            path: path.clone(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for GlogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlogError::Io {
+                path,
+                source,
+            } => write!(f, "couldn't write {}: {}", path.to_string_lossy(), source),
+            GlogError::InvalidFlag {
+                flag,
+                reason,
+            } => write!(f, "invalid value for flag {}: {}", flag, reason),
+            GlogError::UnknownFlag {
+                flag,
+            } => write!(f, "unknown flag {}", flag),
+            GlogError::SetLogger(why) => write!(f, "couldn't register logger: {}", why),
+        }
+    }
+}
+
+impl Error for GlogError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GlogError::Io {
+                source, ..
+            } => Some(source),
+            GlogError::InvalidFlag {
+                ..
+            }
+            | GlogError::UnknownFlag {
+                ..
+            } => None,
+            GlogError::SetLogger(why) => Some(why),
+        }
+    }
+}
+
+impl From<SetLoggerError> for GlogError {
+    fn from(why: SetLoggerError) -> Self {
+        GlogError::SetLogger(why)
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Once;
+
+static INSTALL: Once = Once::new();
+
+/// Flush all log destinations when the process exits normally or is interrupted
+pub(crate) fn install() {
+    INSTALL.call_once(|| {
+        #[cfg(target_family = "unix")]
+        unix::install();
+    });
+}
+
+#[cfg(target_family = "unix")]
+mod unix {
+    use std::panic;
+
+    use nix::{
+        libc,
+        sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal},
+    };
+
+    extern "C" fn flush_at_exit() {
+        // unwinding out of an exit handler is undefined behavior
+        let _ = panic::catch_unwind(crate::flush);
+    }
+
+    extern "C" fn flush_on_signal(signal: libc::c_int) {
+        // the interrupted thread may hold the locks used by flush
+        crate::flush_log_files_unsafe();
+        // SAFETY: restoring the default disposition and raising the signal again only uses async signal safe functions
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+
+    pub(super) fn install() {
+        // SAFETY: flush_at_exit is a plain function which is valid for the whole runtime of the process
+        unsafe {
+            libc::atexit(flush_at_exit);
+        }
+        let action = SigAction::new(SigHandler::Handler(flush_on_signal), SaFlags::empty(), SigSet::empty());
+        for signal in [Signal::SIGINT, Signal::SIGTERM] {
+            // SAFETY: flush_on_signal only uses async signal safe functions
+            match unsafe { signal::sigaction(signal, &action) } {
+                // keep handlers installed by the application
+                Ok(previous) if previous.handler() != SigHandler::SigDfl => unsafe {
+                    let _ = signal::sigaction(signal, &previous);
+                },
+                _ => {}
+            }
+        }
+    }
+}
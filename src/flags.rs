@@ -1,6 +1,14 @@
-use std::{env::temp_dir, ffi::OsString, path::PathBuf};
+use std::{
+    ffi::{OsStr, OsString},
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
 
+use chrono::{DateTime, FixedOffset, Local, Utc};
 use log::Level;
+use termcolor::ColorChoice;
+
+use crate::GlogError;
 
 /// The flag structure used to initialize glog.
 ///
@@ -12,9 +20,8 @@ use log::Level;
 ///
 /// This are the defaults for each flag:
 /// ```
-/// use std::{env::temp_dir, ffi::OsString, path::PathBuf};
 /// use log::*;
-/// use glog::Flags;
+/// use glog::{Backpressure, ColorMode, Flags, PrefixId, Timezone};
 ///
 /// let flags = Flags::default();
 ///
@@ -23,7 +30,18 @@ use log::Level;
 /// assert!(flags.log_backtrace_at.is_none());
 /// assert_eq!(flags.logtostderr, false);
 /// assert_eq!(flags.alsologtostderr, false);
-/// assert_eq!(flags.log_dir, [temp_dir(), PathBuf::from("")].iter().collect::<PathBuf>().into_os_string());
+/// assert!(flags.log_dir.is_empty());
+/// assert_eq!(flags.temp_log_dir, false);
+/// assert!(flags.alsologtoemail.is_none());
+/// assert!(flags.logemaillevel.is_none());
+/// assert_eq!(flags.v, 0);
+/// assert_eq!(flags.log_prefix_id, PrefixId::ThreadId);
+/// assert_eq!(flags.logbufsecs, 0);
+/// assert_eq!(flags.logbuflevel, Level::Info);
+/// assert_eq!(flags.stderr_color, ColorMode::Auto);
+/// assert_eq!(flags.async_queue_size, 0);
+/// assert_eq!(flags.backpressure, Backpressure::Block);
+/// assert_eq!(flags.log_timezone, Timezone::Local);
 /// ```
 #[derive(Debug, Clone)]
 pub struct Flags {
@@ -35,13 +53,254 @@ pub struct Flags {
     /// Optionally log a backtrace at `filename:line` log invocation.
     /// The log level has to be enabled for it to work.
     /// Will be written in the log file with the lowest severity.
+    ///
+    /// Several locations can be given as a comma separated list. The file name and the line may
+    /// contain the wildcards `*` for any number of characters and `?` for a single character,
+    /// e.g. `parser.rs:*`. Patterns containing a `/` are matched against the whole path as
+    /// reported by the compiler instead of the file name, e.g. `*/db/*.rs:120`.
     pub log_backtrace_at: Option<String>,
     /// Log to stderr instead of logfiles
+    ///
+    /// On Windows consoles messages are written as UTF-16, so non-ASCII text shows up correctly
+    /// regardless of the console code page. Redirected stderr receives UTF-8 like the log files.
     pub logtostderr: bool,
     /// Log to stderr and logfiles
     pub alsologtostderr: bool,
-    /// Directory in which to store the log files
+    /// Directory in which to store the log files, empty for the platform default
+    ///
+    /// Unlike glog, which always defaults to the temporary directory, the default is the first
+    /// of these directories which is available:
+    ///
+    /// - `$LOGS_DIRECTORY` as set by systemd for units with `LogsDirectory=`
+    /// - `$XDG_STATE_HOME/<program>/logs` on unix platforms other than macOS
+    /// - `/var/log/<program>` on unix platforms other than macOS if `/var/log` is writable
+    /// - `~/.local/state/<program>/logs` on unix platforms other than macOS
+    /// - `~/Library/Logs/<program>` on macOS
+    /// - `%LOCALAPPDATA%\<program>\logs` on Windows
+    /// - the temporary directory
     pub log_dir: OsString,
+    /// Default to the temporary directory like glog if [`log_dir`](Flags::log_dir) is empty
+    pub temp_log_dir: bool,
+    /// Comma separated list of addresses which receive messages at or above [`logemaillevel`](Flags::logemaillevel)
+    /// by email
+    pub alsologtoemail: Option<String>,
+    /// Minimum level (inclusive) of messages sent by email, `None` disables emails
+    pub logemaillevel: Option<Level>,
+    /// Maximum verbosity (inclusive) of [`vlog!`](crate::vlog) messages that should be logged
+    pub v: i32,
+    /// Which ids identify the origin of a message in the log prefix
+    pub log_prefix_id: PrefixId,
+    /// Buffer messages for the log files for up to this many seconds, 0 disables buffering
+    ///
+    /// Unlike in glog buffering is disabled by default. Rust never drops the logger, so
+    /// buffered messages are lost on exit unless glog gets [flushed](crate::flush()),
+    /// [shut down](crate::shutdown()) or [`flush_on_exit`](crate::Glog::flush_on_exit) is
+    /// enabled. Buffered messages are not written by
+    /// [`flush_log_files_unsafe`](crate::flush_log_files_unsafe), so they are lost when the
+    /// process is terminated by a signal, even with `flush_on_exit`.
+    pub logbufsecs: u32,
+    /// Buffer messages at this level or less severe, more severe messages are written immediately
+    pub logbuflevel: Level,
+    /// When [`colorlogtostderr`](Flags::colorlogtostderr) emits colors
+    pub stderr_color: ColorMode,
+    /// Write the log files on a background thread through a queue of this many messages, 0 writes
+    /// them on the logging thread
+    ///
+    /// The messages are still formatted on the logging thread, so the prefix is the same either
+    /// way. The queue is created by [`init`](crate::Glog::init) and is drained whenever glog is
+    /// [flushed](crate::flush()). Queued messages are not written by
+    /// [`flush_log_files_unsafe`](crate::flush_log_files_unsafe), so they are lost when the
+    /// process is terminated by a signal.
+    pub async_queue_size: usize,
+    /// What happens to a message for the log files if the queue of
+    /// [`async_queue_size`](Flags::async_queue_size) is full
+    pub backpressure: Backpressure,
+    /// Timezone of the timestamps in the prefix, the file headers and the crash reports
+    ///
+    /// On the command line `local`, `utc` or a fixed offset like `+05:30`. The names of the log
+    /// files keep the local time.
+    pub log_timezone: Timezone,
+}
+
+/// How the logging thread reacts to a full queue of the background writer
+///
+/// Set with [`backpressure`](Flags::backpressure).
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{Backpressure, Flags};
+///
+/// glog::new().init(Flags {
+///         async_queue_size: 1024,
+///         backpressure: Backpressure::DropOldest,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// info!("Written to the log file by the glog-writer thread");
+/// glog::flush();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait until the writer made room, no message is lost but logging can stall
+    Block,
+    /// Drop the new message, logging never waits
+    Drop,
+    /// Drop the oldest queued message to make room for the new one, logging never waits
+    DropOldest,
+}
+
+/// When colors are written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// If stderr is a terminal which supports colors according to the `TERM` and `NO_COLOR`
+    /// environment variables
+    ///
+    /// On Windows consoles without support for escape sequences, like `cmd.exe` before
+    /// Windows 10, the colors are set through the console API.
+    Auto,
+    /// Always write colors, e.g. for CI systems or pagers which render them
+    Always,
+    /// Never write colors
+    Never,
+}
+
+impl ColorMode {
+    pub(crate) fn color_choice(&self) -> ColorChoice {
+        match self {
+            // escape sequences would end up in files and pipes stderr is redirected to
+            ColorMode::Auto if !io::stderr().is_terminal() => ColorChoice::Never,
+            ColorMode::Auto => ColorChoice::Auto,
+            ColorMode::Always => ColorChoice::Always,
+            ColorMode::Never => ColorChoice::Never,
+        }
+    }
+}
+
+/// Ids which can be shown in the log prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixId {
+    /// Id of the logging thread, like in glog
+    ThreadId,
+    /// Id of the logging process
+    ProcessId,
+    /// Id of the logging process followed by the id of the logging thread
+    Both,
+}
+
+impl PrefixId {
+    pub(crate) fn format_name(&self) -> &'static str {
+        match self {
+            PrefixId::ThreadId => "threadid",
+            PrefixId::ProcessId => "processid",
+            PrefixId::Both => "processid threadid",
+        }
+    }
+}
+
+/// Which clock the timestamps in the prefix are taken from
+///
+/// Set with [`timestamp_clock`](crate::Glog::timestamp_clock).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampClock {
+    /// The local wall-clock time, like in glog
+    WallClock,
+    /// Nanoseconds of a monotonic clock since glog has been created, e.g. `12345678901ns`
+    ///
+    /// The monotonic clock is immune to changes of the system time, like NTP adjustments, so
+    /// the difference between two messages is always the time elapsed between them.
+    Monotonic,
+    /// The wall-clock time followed by the monotonic nanoseconds
+    Both,
+}
+
+/// Timezone of the timestamps glog writes, set with [`log_timezone`](Flags::log_timezone)
+///
+/// # Example
+///
+/// ```
+/// use chrono::FixedOffset;
+/// use log::*;
+/// use glog::{Flags, Timezone};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         log_timezone: Timezone::Fixed(FixedOffset::east_opt(9 * 3600).unwrap()),
+///         ..Default::default()
+///     }).unwrap();
+///
+/// info!("Logged in Tokyo time");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone {
+    /// The local timezone of the machine, like in glog
+    Local,
+    /// Coordinated universal time, like `log_utc_time` in glog
+    Utc,
+    /// A fixed offset from UTC, e.g. the canonical timezone of a team spread across several
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// Format `time` in this timezone
+    pub(crate) fn format(&self, time: DateTime<Local>, format: &str) -> String {
+        match self {
+            Timezone::Local => time.format(format).to_string(),
+            Timezone::Utc => time.with_timezone(&Utc).format(format).to_string(),
+            Timezone::Fixed(offset) => time.with_timezone(offset).format(format).to_string(),
+        }
+    }
+}
+
+/// Parse an offset like `+05:30`, `-0800` or `+01`
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = value[1..].replace(':', "");
+    if !digits.chars().all(|c| c.is_ascii_digit()) || !(digits.len() == 2 || digits.len() == 4) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = if digits.len() == 4 { digits[2..].parse().ok()? } else { 0 };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// How the source file of a log invocation is shown in the prefix
+///
+/// Set with [`source_path`](crate::Glog::source_path).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourcePath {
+    /// Only the file name, like in glog
+    FileName,
+    /// The path as reported by the compiler
+    Full,
+    /// The path starting at the `src` directory of the crate, e.g. `src/net/mod.rs`
+    ///
+    /// Paths without a `src` directory are shown in full.
+    CrateRelative,
+}
+
+impl SourcePath {
+    pub(crate) fn format(&self, path: &str) -> String {
+        match self {
+            SourcePath::FileName => Path::new(path).file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            SourcePath::Full => path.to_owned(),
+            SourcePath::CrateRelative => {
+                let components = Path::new(path).components().collect::<Vec<_>>();
+                match components.iter().rposition(|component| component.as_os_str() == "src") {
+                    Some(src) => components[src..].iter().collect::<PathBuf>().to_string_lossy().into_owned(),
+                    None => path.to_owned(),
+                }
+            }
+        }
+    }
 }
 
 impl Default for Flags {
@@ -52,13 +311,358 @@ impl Default for Flags {
             log_backtrace_at: None,
             logtostderr: false,
             alsologtostderr: false,
-            log_dir: [
-                temp_dir().into_os_string(),
-                OsString::from(""), // Users may not append a / or \ to their env vars
-            ]
-            .iter()
-            .collect::<PathBuf>()
-            .into_os_string(),
+            log_dir: OsString::new(),
+            temp_log_dir: false,
+            alsologtoemail: None,
+            logemaillevel: None,
+            v: 0,
+            log_prefix_id: PrefixId::ThreadId,
+            logbufsecs: 0,
+            logbuflevel: Level::Info,
+            stderr_color: ColorMode::Auto,
+            async_queue_size: 0,
+            backpressure: Backpressure::Block,
+            log_timezone: Timezone::Local,
+        }
+    }
+}
+
+const BOOL_FLAGS: [&str; 4] = ["colorlogtostderr", "logtostderr", "alsologtostderr", "temp_log_dir"];
+const VALUE_FLAGS: [&str; 13] = [
+    "minloglevel",
+    "log_backtrace_at",
+    "log_dir",
+    "alsologtoemail",
+    "logemaillevel",
+    "v",
+    "log_prefix_id",
+    "logbufsecs",
+    "logbuflevel",
+    "stderr_color",
+    "async_queue_size",
+    "backpressure",
+    "log_timezone",
+];
+
+impl Flags {
+    /// Parse the flags from command line arguments in the style of [`gflags`]
+    ///
+    /// Accepts `--name=value` and `--name value` as well as `--name` and `--noname` for boolean
+    /// flags, each with one or two leading dashes. Levels can be given by name or by the glog
+    /// severity number. Flags which aren't set keep their [default](Flags::default) value.
+    /// All arguments which aren't flags, and all arguments after `--`, are returned in their
+    /// original order, so they can be passed on to the argument parser of the application.
+    ///
+    /// [`gflags`]: https://github.com/gflags/gflags
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// let args = ["my_app", "--logtostderr", "--v=2", "--minloglevel", "WARN", "input.txt"];
+    /// let (flags, remaining) = Flags::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+    ///
+    /// assert!(flags.logtostderr);
+    /// assert_eq!(flags.v, 2);
+    /// assert_eq!(flags.minloglevel, Level::Warn);
+    /// assert_eq!(remaining, ["my_app", "input.txt"]);
+    /// ```
+    ///
+    /// Flags of the application have to follow `--`:
+    ///
+    /// ```
+    /// use glog::{Flags, GlogError};
+    ///
+    /// let args = ["my_app", "--log_dir=/var/log/my_app", "--", "--threads=4"];
+    /// let (flags, remaining) = Flags::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+    /// assert_eq!(flags.log_dir, "/var/log/my_app/");
+    /// assert_eq!(remaining, ["my_app", "--", "--threads=4"]);
+    ///
+    /// let args = ["my_app", "--threads=4"];
+    /// let result = Flags::from_args(args.iter().map(|arg| arg.to_string()));
+    /// assert!(matches!(result, Err(GlogError::UnknownFlag { flag }) if flag == "threads"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`GlogError::InvalidFlag`] if a value can't be parsed or is missing and with
+    /// [`GlogError::UnknownFlag`] for flags glog doesn't know before `--`.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<(Flags, Vec<String>), GlogError> {
+        let mut flags = Flags::default();
+        let mut remaining = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // like in gflags everything after -- is left alone
+            if arg == "--" {
+                remaining.push(arg);
+                remaining.extend(args);
+                break;
+            }
+            let flag = match arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) {
+                Some(flag) => flag,
+                None => {
+                    remaining.push(arg);
+                    continue;
+                }
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (flag, None),
+            };
+            if let Some(bool_flag) = BOOL_FLAGS.iter().find(|bool_flag| **bool_flag == name) {
+                let enabled = match value.as_deref() {
+                    None => true,
+                    Some(value) => parse_bool(bool_flag, value)?,
+                };
+                flags.set_bool(bool_flag, enabled);
+            } else if let Some(bool_flag) = BOOL_FLAGS
+                .iter()
+                .find(|bool_flag| name.strip_prefix("no") == Some(**bool_flag))
+            {
+                flags.set_bool(bool_flag, false);
+            } else if let Some(value_flag) = VALUE_FLAGS.iter().find(|value_flag| **value_flag == name) {
+                let value = match value.or_else(|| args.next()) {
+                    Some(value) => value,
+                    None => return Err(invalid(value_flag, "missing value".to_owned())),
+                };
+                flags.set_value(value_flag, value)?;
+            } else {
+                return Err(GlogError::UnknownFlag {
+                    flag: name.to_owned(),
+                });
+            }
+        }
+        flags.validate()?;
+        Ok((flags, remaining))
+    }
+
+    fn set_bool(&mut self, flag: &str, enabled: bool) {
+        match flag {
+            "colorlogtostderr" => self.colorlogtostderr = enabled,
+            "logtostderr" => self.logtostderr = enabled,
+            "alsologtostderr" => self.alsologtostderr = enabled,
+            _ => self.temp_log_dir = enabled,
+        }
+    }
+
+    fn set_value(&mut self, flag: &'static str, value: String) -> Result<(), GlogError> {
+        match flag {
+            "minloglevel" => self.minloglevel = parse_level(flag, &value)?,
+            "log_backtrace_at" => self.log_backtrace_at = Some(value).filter(|value| !value.is_empty()),
+            "log_dir" => {
+                let mut log_dir = PathBuf::from(value);
+                // the file names are appended to the directory
+                log_dir.push("");
+                self.log_dir = log_dir.into_os_string();
+            }
+            "alsologtoemail" => self.alsologtoemail = Some(value).filter(|value| !value.is_empty()),
+            "logemaillevel" => self.logemaillevel = Some(parse_level(flag, &value)?),
+            "v" => {
+                self.v = value
+                    .parse()
+                    .map_err(|_| invalid(flag, format!("expected a number but got {}", value)))?
+            }
+            "log_prefix_id" => {
+                self.log_prefix_id = match value.as_str() {
+                    "threadid" => PrefixId::ThreadId,
+                    "processid" => PrefixId::ProcessId,
+                    "both" => PrefixId::Both,
+                    _ => {
+                        return Err(invalid(
+                            flag,
+                            format!("expected threadid, processid or both but got {}", value),
+                        ))
+                    }
+                }
+            }
+            "logbufsecs" => {
+                self.logbufsecs = value
+                    .parse()
+                    .map_err(|_| invalid(flag, format!("expected a number of seconds but got {}", value)))?
+            }
+            "logbuflevel" => self.logbuflevel = parse_level(flag, &value)?,
+            "stderr_color" => {
+                self.stderr_color = match value.as_str() {
+                    "auto" => ColorMode::Auto,
+                    "always" => ColorMode::Always,
+                    "never" => ColorMode::Never,
+                    _ => return Err(invalid(flag, format!("expected auto, always or never but got {}", value))),
+                }
+            }
+            "async_queue_size" => {
+                self.async_queue_size = value
+                    .parse()
+                    .map_err(|_| invalid(flag, format!("expected a number of messages but got {}", value)))?
+            }
+            "log_timezone" => {
+                self.log_timezone = match value.as_str() {
+                    "local" => Timezone::Local,
+                    "utc" | "UTC" => Timezone::Utc,
+                    _ => Timezone::Fixed(parse_offset(&value).ok_or_else(|| {
+                        invalid(
+                            flag,
+                            format!("expected local, utc or an offset like +05:30 but got {}", value),
+                        )
+                    })?),
+                }
+            }
+            _ => {
+                self.backpressure = match value.as_str() {
+                    "block" => Backpressure::Block,
+                    "drop" => Backpressure::Drop,
+                    "drop_oldest" => Backpressure::DropOldest,
+                    _ => {
+                        return Err(invalid(
+                            flag,
+                            format!("expected block, drop or drop_oldest but got {}", value),
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), GlogError> {
+        if let Some(log_backtrace_at) = &self.log_backtrace_at {
+            for location in backtrace_locations(log_backtrace_at) {
+                let valid = match location.rsplit_once(':') {
+                    Some((file_name, line)) => {
+                        !file_name.is_empty()
+                            && (line.parse::<u32>().is_ok()
+                                || (!line.is_empty() && line.chars().all(|c| c.is_ascii_digit() || c == '*' || c == '?')))
+                    }
+                    None => false,
+                };
+                if !valid {
+                    return Err(GlogError::InvalidFlag {
+                        flag: "log_backtrace_at",
+                        reason: format!("expected filename:line but got {}", location),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn backtrace_locations(log_backtrace_at: &str) -> impl Iterator<Item = &str> {
+    log_backtrace_at
+        .split(',')
+        .map(str::trim)
+        .filter(|location| !location.is_empty())
+}
+
+/// Whether a log invocation in the source file at `path` and `line` matches one of the
+/// locations of [`log_backtrace_at`](Flags::log_backtrace_at)
+pub(crate) fn matches_backtrace_at(log_backtrace_at: &str, path: &str, line: u32) -> bool {
+    let file_name = Path::new(path).file_name().unwrap_or_default().to_string_lossy();
+    let line = line.to_string();
+    backtrace_locations(log_backtrace_at).any(|location| {
+        let (file_pattern, line_pattern) = match location.rsplit_once(':') {
+            Some(location) => location,
+            None => return false,
+        };
+        let file = if file_pattern.contains('/') { path } else { &file_name };
+        wildcard_match(file_pattern, file) && wildcard_match(line_pattern, &line)
+    })
+}
+
+/// Match `text` against `pattern` with `*` for any number of characters and `?` for one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // where the last `*` is in the pattern and the text it has been tried at
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // let the `*` match one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
         }
     }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn invalid(flag: &'static str, reason: String) -> GlogError {
+    GlogError::InvalidFlag {
+        flag,
+        reason,
+    }
+}
+
+fn parse_bool(flag: &'static str, value: &str) -> Result<bool, GlogError> {
+    match value {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" => Ok(false),
+        _ => Err(invalid(flag, format!("expected true or false but got {}", value))),
+    }
+}
+
+fn parse_level(flag: &'static str, value: &str) -> Result<Level, GlogError> {
+    // the severity numbers of glog, there is no FATAL level so 3 maps to ERROR
+    match value {
+        "0" => Ok(Level::Info),
+        "1" => Ok(Level::Warn),
+        "2" | "3" => Ok(Level::Error),
+        "WARNING" | "warning" => Ok(Level::Warn),
+        _ => value
+            .parse()
+            .map_err(|_| invalid(flag, format!("expected a level but got {}", value))),
+    }
+}
+
+/// The platform specific default directory for the log files of `program`
+///
+/// Returns `None` if no directory applies and the temporary directory should be used.
+pub(crate) fn platform_log_dir(program: &OsStr) -> Option<PathBuf> {
+    if let Some(logs_directory) = std::env::var_os("LOGS_DIRECTORY").filter(|dir| !dir.is_empty()) {
+        // systemd passes a colon separated list if several directories are configured
+        let logs_directory = logs_directory.to_string_lossy().into_owned();
+        return logs_directory.split(':').next().map(PathBuf::from);
+    }
+    user_log_dir(program)
+}
+
+#[cfg(all(target_family = "unix", not(target_os = "macos")))]
+fn user_log_dir(program: &OsStr) -> Option<PathBuf> {
+    use nix::unistd::{access, AccessFlags};
+
+    if let Some(state_home) = std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(Path::new(&state_home).join(program).join("logs"));
+    }
+    if access("/var/log", AccessFlags::W_OK).is_ok() {
+        return Some(Path::new("/var/log").join(program));
+    }
+    let home = std::env::var_os("HOME").filter(|dir| !dir.is_empty())?;
+    Some(Path::new(&home).join(".local/state").join(program).join("logs"))
+}
+
+#[cfg(target_os = "macos")]
+fn user_log_dir(program: &OsStr) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").filter(|dir| !dir.is_empty())?;
+    Some(Path::new(&home).join("Library/Logs").join(program))
+}
+
+#[cfg(target_family = "windows")]
+fn user_log_dir(program: &OsStr) -> Option<PathBuf> {
+    let local_app_data = std::env::var_os("LOCALAPPDATA").filter(|dir| !dir.is_empty())?;
+    Some(Path::new(&local_app_data).join(program).join("logs"))
+}
+
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+fn user_log_dir(_program: &OsStr) -> Option<PathBuf> {
+    None
 }
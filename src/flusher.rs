@@ -0,0 +1,203 @@
+#[cfg(feature = "flush-task")]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    task::{Context, Poll},
+};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Future flushing all log destinations periodically until a shutdown signal
+///
+/// Returned by [`periodic_flush`]. The destinations are flushed on a background thread, so the
+/// runtime polling this future is never blocked by slow log files. Once the shutdown signal
+/// completes they are flushed a last time, the thread is stopped and the future completes.
+/// Dropping the future, e.g. by aborting its task, stops the thread as well.
+#[cfg(feature = "flush-task")]
+#[must_use = "futures do nothing unless polled, the periodic flush stops once this is dropped"]
+pub struct PeriodicFlush<S> {
+    shutdown: Pin<Box<S>>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "flush-task")]
+impl<S> PeriodicFlush<S> {
+    fn stop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(feature = "flush-task")]
+impl<S: Future> Future for PeriodicFlush<S> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.stop.is_none() {
+            return Poll::Ready(());
+        }
+        if self.shutdown.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.stop();
+        Poll::Ready(())
+    }
+}
+
+#[cfg(feature = "flush-task")]
+impl<S> Drop for PeriodicFlush<S> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Flush all log destinations every `interval` and once more when `shutdown` completes
+///
+/// The returned future doesn't depend on a specific async runtime, so a service using
+/// [`logbufsecs`](crate::Flags::logbufsecs) or the
+/// [asynchronous writer](crate::Flags::async_queue_size) can spawn it as a task instead of
+/// hand-rolling the flushing, e.g. with tokio:
+///
+/// ```text
+/// tokio::spawn(glog::periodic_flush(Duration::from_secs(5), tokio::signal::ctrl_c()));
+/// ```
+///
+/// Flushing starts right away, even before the future is polled for the first time.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use log::*;
+/// use glog::Flags;
+/// # fn block_on<F: std::future::Future>(future: F) -> F::Output {
+/// #     use std::{sync::Arc, task::{Context, Poll, Wake}, thread::{self, Thread}};
+/// #     struct Unpark(Thread);
+/// #     impl Wake for Unpark {
+/// #         fn wake(self: Arc<Self>) {
+/// #             self.0.unpark()
+/// #         }
+/// #     }
+/// #     let waker = Arc::new(Unpark(thread::current())).into();
+/// #     let mut future = Box::pin(future);
+/// #     loop {
+/// #         match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+/// #             Poll::Ready(output) => return output,
+/// #             Poll::Pending => thread::park(),
+/// #         }
+/// #     }
+/// # }
+///
+/// glog::new().init(Flags {
+///         logbufsecs: 30,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// // a signal handler in a real service
+/// let shutdown = async {};
+/// let flush = glog::periodic_flush(Duration::from_secs(5), shutdown);
+///
+/// info!("Flushed within 5 seconds and on shutdown");
+///
+/// // spawned as a task of the async runtime in a real service
+/// block_on(flush);
+/// ```
+#[cfg(feature = "flush-task")]
+pub fn periodic_flush<S: Future>(interval: Duration, shutdown: S) -> PeriodicFlush<S> {
+    let (stop, stopped) = mpsc::channel::<()>();
+    let thread = thread::Builder::new()
+        .name("glog-flush".to_owned())
+        .spawn(move || loop {
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => crate::flush(),
+                _ => {
+                    crate::flush();
+                    break;
+                }
+            }
+        })
+        .ok();
+    PeriodicFlush {
+        shutdown: Box::pin(shutdown),
+        stop: Some(stop),
+        thread,
+    }
+}
+
+struct State {
+    last_buffered: Option<Instant>,
+    stopped: bool,
+}
+
+/// Flushes the log destinations once no message has been buffered for the idle period
+pub(crate) struct IdleFlush {
+    idle: Duration,
+    state: Mutex<State>,
+    buffered: Condvar,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl IdleFlush {
+    pub(crate) fn new(idle: Duration) -> Self {
+        IdleFlush {
+            idle,
+            state: Mutex::new(State {
+                last_buffered: None,
+                stopped: false,
+            }),
+            buffered: Condvar::new(),
+            thread: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn idle(&self) -> Duration {
+        self.idle
+    }
+
+    /// Record that a message has been buffered instead of written
+    pub(crate) fn buffered(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.last_buffered.replace(Instant::now()).is_none() {
+            self.buffered.notify_one();
+        }
+    }
+
+    /// Stop the background thread and wait until it has exited
+    pub(crate) fn stop(&self) {
+        self.state.lock().unwrap().stopped = true;
+        self.buffered.notify_one();
+        let thread = self.thread.lock().unwrap().take();
+        if let Some(thread) = thread.filter(|thread| thread.thread().id() != thread::current().id()) {
+            let _ = thread.join();
+        }
+    }
+
+    /// Wait for buffered messages on a background thread and flush them once logging is idle
+    pub(crate) fn spawn(self: Arc<Self>) {
+        let flusher = self.clone();
+        let thread = thread::Builder::new().name("glog-idle-flush".to_owned()).spawn(move || loop {
+            let mut state = flusher.state.lock().unwrap();
+            match state.last_buffered {
+                _ if state.stopped => return,
+                None => drop(flusher.buffered.wait(state).unwrap()),
+                Some(instant) if instant.elapsed() >= flusher.idle => {
+                    state.last_buffered = None;
+                    drop(state);
+                    crate::flush();
+                }
+                Some(instant) => {
+                    let timeout = flusher.idle - instant.elapsed();
+                    drop(flusher.buffered.wait_timeout(state, timeout).unwrap());
+                }
+            }
+        });
+        *self.thread.lock().unwrap() = thread.ok();
+    }
+}
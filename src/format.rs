@@ -0,0 +1,103 @@
+//! Formatters for destinations which don't use the glog text format
+//!
+//! Use them with [`add_writer_with_formatter`](crate::add_writer_with_formatter) or in a
+//! [`Sink`](crate::Sink).
+//!
+//! # Example
+//!
+//! ```
+//! use log::*;
+//! use glog::{format, Flags};
+//!
+//! glog::new().init(Flags::default()).unwrap();
+//!
+//! // the log files keep the glog format while stdout only shows the message
+//! glog::add_writer_with_formatter(Box::new(std::io::stdout()), format::message);
+//!
+//! info!("Hello");
+//! // Hello
+//! ```
+
+use std::fmt::Write;
+
+use crate::RecordData;
+
+/// Only the message text
+pub fn message(record: &RecordData) -> String {
+    record.message.clone()
+}
+
+/// A single line JSON object with the fields of the record
+///
+/// ```
+/// use log::Level;
+/// use glog::{format, RecordData};
+///
+/// # let record = RecordData {
+/// #     level: Level::Info,
+/// #     target: "app".to_owned(),
+/// #     module_path: Some("app".to_owned()),
+/// #     file: Some("src/main.rs".to_owned()),
+/// #     line: Some(7),
+/// #     thread_name: Some("main".to_owned()),
+/// #     thread_id: 123,
+/// #     timestamp: chrono::Local::now(),
+/// #     message: "Hello \"world\"".to_owned(),
+/// #     key_values: vec![("user".to_owned(), "alice".to_owned())],
+/// # };
+/// let json = format::json(&record);
+/// assert!(json.contains(r#""message":"Hello \"world\"""#));
+/// assert!(json.contains(r#""user":"alice""#));
+/// ```
+pub fn json(record: &RecordData) -> String {
+    let mut json = String::new();
+    json.push('{');
+    push_field(&mut json, "timestamp", &record.timestamp.to_rfc3339());
+    json.push(',');
+    push_field(&mut json, "level", record.level.as_str());
+    json.push(',');
+    push_field(&mut json, "target", &record.target);
+    if let Some(file) = &record.file {
+        json.push(',');
+        push_field(&mut json, "file", file);
+    }
+    if let Some(line) = record.line {
+        let _ = write!(json, ",\"line\":{}", line);
+    }
+    if let Some(thread_name) = &record.thread_name {
+        json.push(',');
+        push_field(&mut json, "thread_name", thread_name);
+    }
+    let _ = write!(json, ",\"thread_id\":{},", record.thread_id);
+    push_field(&mut json, "message", &record.message);
+    for (key, value) in &record.key_values {
+        json.push(',');
+        push_field(&mut json, key, value);
+    }
+    json.push('}');
+    json
+}
+
+fn push_field(json: &mut String, key: &str, value: &str) {
+    push_string(json, key);
+    json.push(':');
+    push_string(json, value);
+}
+
+fn push_string(json: &mut String, text: &str) {
+    json.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            control if control.is_control() => {
+                let _ = write!(json, "\\u{:04x}", control as u32);
+            }
+            _ => json.push(character),
+        }
+    }
+    json.push('"');
+}
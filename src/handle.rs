@@ -0,0 +1,171 @@
+use log::{Level, Log};
+
+use crate::{Flags, Glog, GlogError, LOGGER};
+
+/// Handle to the initialized logger
+///
+/// Gives access to the effective flags and allows changing some of them while the application is
+/// running. Obtain one with [`logger`].
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// let logger = glog::logger().unwrap();
+/// assert_eq!(logger.flags().minloglevel, Level::Info);
+///
+/// logger.set_minloglevel(Level::Warn);
+/// info!("This won't be logged anymore");
+/// warn!("But this will");
+///
+/// // Dump the current configuration, e.g. for a support bundle
+/// println!("{:?}", logger);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LoggerHandle {
+    glog: &'static Glog,
+}
+
+impl LoggerHandle {
+    /// A copy of the flags currently in effect
+    pub fn flags(&self) -> Flags {
+        self.glog.flags().clone()
+    }
+
+    /// Change the minimum level (inclusive) that should be logged
+    pub fn set_minloglevel(&self, level: Level) {
+        self.glog.update_flags(|flags| flags.minloglevel = level);
+        log::set_max_level(level.to_level_filter());
+    }
+
+    /// Change whether levels more severe than `Info` are colorized on stderr
+    pub fn set_colorlogtostderr(&self, colorlogtostderr: bool) {
+        self.glog.update_flags(|flags| flags.colorlogtostderr = colorlogtostderr);
+    }
+
+    /// Change whether messages are written to stderr instead of the log files
+    ///
+    /// If the process started with [`logtostderr`](Flags::logtostderr) the log files are created
+    /// when switching to them for the first time. Switching back to stderr keeps the files open
+    /// for the next switch. The [asynchronous writer](Flags::async_queue_size) is only used if
+    /// the process started with log files.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    /// assert!(glog::log_file_path(Level::Info).is_none());
+    ///
+    /// let logger = glog::logger().unwrap();
+    /// logger.set_logtostderr(false).unwrap();
+    /// info!("Written to the log file created on demand");
+    /// assert!(glog::log_file_path(Level::Info).is_some());
+    /// ```
+    pub fn set_logtostderr(&self, logtostderr: bool) -> Result<(), GlogError> {
+        self.glog.set_logtostderr(logtostderr)
+    }
+
+    /// Replace all flags at once, used by the admin handler to apply a request entirely or not at all
+    #[cfg(feature = "admin")]
+    pub(crate) fn set_flags(&self, flags: Flags) -> Result<(), GlogError> {
+        self.glog.set_flags(flags)
+    }
+
+    /// Change whether messages are written to stderr in addition to the log files
+    ///
+    /// Has no effect while messages are written to stderr only, see
+    /// [`set_logtostderr`](LoggerHandle::set_logtostderr).
+    pub fn set_alsologtostderr(&self, alsologtostderr: bool) {
+        self.glog.update_flags(|flags| flags.alsologtostderr = alsologtostderr);
+    }
+
+    /// Change the maximum verbosity of [`vlog!`](crate::vlog) messages that should be logged
+    pub fn set_v(&self, v: i32) {
+        self.glog.update_flags(|flags| flags.v = v);
+    }
+
+    /// Change the `filename:line` log invocations at which a backtrace is logged
+    ///
+    /// Takes the same comma separated list of locations with wildcards as
+    /// [`log_backtrace_at`](Flags::log_backtrace_at), e.g. `parser.rs:*,*/db/*.rs:120`.
+    pub fn set_log_backtrace_at(&self, log_backtrace_at: Option<String>) {
+        self.glog.update_flags(|flags| flags.log_backtrace_at = log_backtrace_at);
+    }
+
+    /// Close the log file of `level` and stop writing to it
+    ///
+    /// This is the counterpart to `SetLogDestination(severity, "")` in glog. Messages of
+    /// `level` are still written to the files of less severe levels.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().init(Flags::default()).unwrap();
+    ///
+    /// let logger = glog::logger().unwrap();
+    /// logger.disable_log_file(Level::Warn);
+    /// assert!(glog::log_file_path(Level::Warn).is_none());
+    ///
+    /// warn!("Only written to the INFO file");
+    ///
+    /// logger.enable_log_file(Level::Warn).unwrap();
+    /// assert!(glog::log_file_path(Level::Warn).is_some());
+    /// ```
+    pub fn disable_log_file(&self, level: Level) {
+        self.glog.disable_log_file(level);
+    }
+
+    /// Create a new log file for `level` after it has been disabled
+    ///
+    /// Does nothing if the file of `level` is still open or glog only logs to stderr.
+    pub fn enable_log_file(&self, level: Level) -> Result<(), GlogError> {
+        self.glog.enable_log_file(level)
+    }
+}
+
+/// Flushes all log destinations when dropped
+///
+/// Returned by [`init_scoped`](crate::Glog::init_scoped).
+#[must_use = "the log destinations are flushed as soon as the guard is dropped"]
+pub struct InitGuard {
+    _private: (),
+}
+
+impl InitGuard {
+    pub(crate) fn new() -> Self {
+        InitGuard {
+            _private: (),
+        }
+    }
+}
+
+impl Drop for InitGuard {
+    fn drop(&mut self) {
+        if let Some(glog) = LOGGER.get() {
+            glog.flush();
+        }
+    }
+}
+
+/// Get a handle to the logger if glog has been initialized
+pub fn logger() -> Option<LoggerHandle> {
+    LOGGER.get().map(|glog| LoggerHandle {
+        glog,
+    })
+}
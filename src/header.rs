@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+
+/// The data written to the header of each log file
+///
+/// Obtain it with [`header_info`](crate::header_info) to show the same information elsewhere,
+/// e.g. on a status page.
+#[derive(Debug, Clone)]
+pub struct HeaderInfo {
+    /// Time at which the logger was created
+    pub start_time: DateTime<Local>,
+    /// Name of the machine the application is running on
+    pub hostname: String,
+    /// Fingerprint set with [`set_application_fingerprint`](crate::Glog::set_application_fingerprint)
+    pub application_fingerprint: Option<String>,
+    /// Id of the logging process
+    pub pid: u32,
+    /// Program and arguments the process was started with
+    pub command_line: Vec<String>,
+    /// Working directory of the process if enabled with
+    /// [`header_working_directory`](crate::Glog::header_working_directory)
+    pub working_directory: Option<PathBuf>,
+}
@@ -0,0 +1,27 @@
+use std::ffi::OsStr;
+
+use log::Level;
+
+/// A destination a message is written to, see [`before_write`](crate::Glog::before_write)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination<'a> {
+    /// Standard error
+    Stderr,
+    /// The log file at this path, of a severity or a [routed](crate::Glog::route_target) target
+    File(&'a OsStr),
+    /// The journal enabled with [`with_journal`](crate::Glog::with_journal)
+    Journal,
+    /// The writer added with [`add_writer`](crate::add_writer) at this position, starting at 0
+    Writer(usize),
+}
+
+/// A write of a message to a single destination, passed to the write hooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteEvent<'a> {
+    /// Level of the message
+    pub level: Level,
+    /// Where the message is written to
+    pub destination: Destination<'a>,
+    /// Bytes written to the destination, including the line break
+    pub bytes: usize,
+}
@@ -0,0 +1,119 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+
+const MAGIC: &[u8; 8] = b"GLOGIDX1";
+const ENTRY_SIZE: usize = 16;
+
+/// Writes the sidecar index of a log file
+///
+/// The index starts with a magic number followed by one entry every `interval` records. Each
+/// entry holds the time in microseconds since the unix epoch and the offset of the record in the
+/// log file, both as little endian 64 bit integers.
+pub(crate) struct IndexWriter {
+    path: OsString,
+    file: File,
+    interval: usize,
+    // records until the next one is added to the index
+    skip: usize,
+}
+
+impl IndexWriter {
+    /// Create the index `<log file>.idx` or continue it if `append` is set
+    pub(crate) fn create(log_file_path: &OsStr, interval: usize, append: bool) -> io::Result<Self> {
+        let mut path = log_file_path.to_os_string();
+        path.push(".idx");
+        let mut file = OpenOptions::new()
+            .append(append)
+            .write(true)
+            .truncate(!append)
+            .create(true)
+            .open(&path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(MAGIC)?;
+        }
+        Ok(IndexWriter {
+            path,
+            file,
+            interval: interval.max(1),
+            skip: 0,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &OsString {
+        &self.path
+    }
+
+    /// Count a record starting at `offset` and add it to the index if it is due
+    pub(crate) fn record(&mut self, offset: u64) -> io::Result<()> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return Ok(());
+        }
+        self.skip = self.interval - 1;
+        let mut entry = [0; ENTRY_SIZE];
+        let now = Utc::now();
+        let micros = now.timestamp() * 1_000_000 + i64::from(now.timestamp_subsec_micros());
+        entry[..8].copy_from_slice(&micros.to_le_bytes());
+        entry[8..].copy_from_slice(&offset.to_le_bytes());
+        self.file.write_all(&entry)
+    }
+}
+
+/// An entry of a log file index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// When the record was written
+    pub timestamp: DateTime<Local>,
+    /// Byte offset of the record in the log file
+    pub offset: u64,
+}
+
+/// Read the index written next to a log file by [`index_log_files`](crate::Glog::index_log_files)
+///
+/// The entries are sorted by time, so the offset to start reading from for a point in time can
+/// be found with a binary search instead of scanning the whole log file.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::{fs::File, io::{Seek, SeekFrom}};
+/// use chrono::{Duration, Local};
+///
+/// let entries = glog::read_log_index("/tmp/server.localhost.nobody.log.INFO.20210401-123456.1234.idx").unwrap();
+/// let since = Local::now() - Duration::minutes(5);
+/// let start = entries.partition_point(|entry| entry.timestamp < since).saturating_sub(1);
+///
+/// let mut log_file = File::open("/tmp/server.localhost.nobody.log.INFO.20210401-123456.1234").unwrap();
+/// log_file.seek(SeekFrom::Start(entries.get(start).map_or(0, |entry| entry.offset))).unwrap();
+/// ```
+pub fn read_log_index<P: AsRef<Path>>(path: P) -> io::Result<Vec<IndexEntry>> {
+    let bytes = fs::read(path)?;
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a glog log file index"));
+    }
+    // a partially written last entry of a killed process is ignored
+    bytes[MAGIC.len()..]
+        .chunks_exact(ENTRY_SIZE)
+        .map(|entry| {
+            let mut timestamp = [0; 8];
+            timestamp.copy_from_slice(&entry[..8]);
+            let mut offset = [0; 8];
+            offset.copy_from_slice(&entry[8..]);
+            let micros = i64::from_le_bytes(timestamp);
+            let timestamp = Utc
+                .timestamp_opt(micros.div_euclid(1_000_000), (micros.rem_euclid(1_000_000) * 1000) as u32)
+                .single()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid timestamp in log file index"))?;
+            Ok(IndexEntry {
+                timestamp: timestamp.with_timezone(&Local),
+                offset: u64::from_le_bytes(offset),
+            })
+        })
+        .collect()
+}
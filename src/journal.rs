@@ -0,0 +1,150 @@
+use std::{fs, io, path::Path};
+
+const MAGIC: &[u8; 8] = b"GLOGJRNL";
+const HEADER_SIZE: usize = 16;
+
+pub(crate) use writer::Journal;
+
+#[cfg(target_family = "unix")]
+mod writer {
+    use std::{ffi::OsString, fs::OpenOptions, io, os::unix::io::AsRawFd, ptr, slice};
+
+    use nix::libc;
+
+    use super::{HEADER_SIZE, MAGIC};
+
+    /// Ring buffer in a shared memory mapping which always holds the latest log output
+    ///
+    /// As the mapping is backed by a file the content survives the process being killed.
+    pub(crate) struct Journal {
+        map: *mut u8,
+        capacity: usize,
+        written: u64,
+    }
+
+    // SAFETY: the mapping is owned exclusively by the journal and only accessed through &mut self
+    unsafe impl Send for Journal {}
+
+    impl Journal {
+        pub(crate) fn create(path: &OsString, capacity: usize) -> io::Result<Self> {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?;
+            file.set_len((HEADER_SIZE + capacity) as u64)?;
+            // SAFETY: the file has just been extended to the size of the mapping
+            let map = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    HEADER_SIZE + capacity,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if map == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            let mut journal = Journal {
+                map: map as *mut u8,
+                capacity,
+                written: 0,
+            };
+            journal.bytes()[..MAGIC.len()].copy_from_slice(MAGIC);
+            journal.store_written();
+            Ok(journal)
+        }
+
+        fn bytes(&mut self) -> &mut [u8] {
+            // SAFETY: map points to a mapping of HEADER_SIZE + capacity bytes
+            unsafe { slice::from_raw_parts_mut(self.map, HEADER_SIZE + self.capacity) }
+        }
+
+        fn store_written(&mut self) {
+            let written = self.written.to_le_bytes();
+            self.bytes()[MAGIC.len()..HEADER_SIZE].copy_from_slice(&written);
+        }
+
+        pub(crate) fn append(&mut self, mut data: &[u8]) {
+            if self.capacity == 0 {
+                return;
+            }
+            if data.len() > self.capacity {
+                self.written += (data.len() - self.capacity) as u64;
+                data = &data[data.len() - self.capacity..];
+            }
+            let capacity = self.capacity;
+            let start = (self.written % capacity as u64) as usize;
+            let first = data.len().min(capacity - start);
+            let bytes = self.bytes();
+            bytes[HEADER_SIZE + start..HEADER_SIZE + start + first].copy_from_slice(&data[..first]);
+            bytes[HEADER_SIZE..HEADER_SIZE + data.len() - first].copy_from_slice(&data[first..]);
+            self.written += data.len() as u64;
+            self.store_written();
+        }
+    }
+
+    impl Drop for Journal {
+        fn drop(&mut self) {
+            // SAFETY: map and capacity describe the mapping created in create
+            unsafe {
+                libc::munmap(self.map as *mut libc::c_void, HEADER_SIZE + self.capacity);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_family = "unix"))]
+mod writer {
+    use std::{ffi::OsString, io};
+
+    pub(crate) struct Journal;
+
+    impl Journal {
+        pub(crate) fn create(_path: &OsString, _capacity: usize) -> io::Result<Self> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "journals need memory mappings"))
+        }
+
+        pub(crate) fn append(&mut self, _data: &[u8]) {}
+    }
+}
+
+/// Read the log output kept in the journal at `path`
+///
+/// The journal of a process which has been killed or crashed holds the tail of its log output,
+/// even if it never reached the log files. If the journal wrapped around the partial first line
+/// is skipped.
+///
+/// # Example
+///
+/// ```no_run
+/// let tail = glog::recover_journal("/tmp/server.journal.1234").unwrap();
+/// println!("Last messages before the crash:\n{}", tail);
+/// ```
+pub fn recover_journal<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    if bytes.len() < HEADER_SIZE || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a glog journal"));
+    }
+    let mut written = [0; 8];
+    written.copy_from_slice(&bytes[MAGIC.len()..HEADER_SIZE]);
+    let written = u64::from_le_bytes(written);
+    let data = &bytes[HEADER_SIZE..];
+    if data.is_empty() {
+        return Ok(String::new());
+    }
+    if written <= data.len() as u64 {
+        return Ok(String::from_utf8_lossy(&data[..written as usize]).into_owned());
+    }
+    let start = (written % data.len() as u64) as usize;
+    let mut tail = data[start..].to_vec();
+    tail.extend_from_slice(&data[..start]);
+    let tail = match tail.iter().position(|byte| *byte == b'\n') {
+        Some(newline) => &tail[newline + 1..],
+        None => &tail[..],
+    };
+    Ok(String::from_utf8_lossy(tail).into_owned())
+}
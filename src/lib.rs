@@ -71,17 +71,37 @@
 //! // D20210401 12:34:56.000050  1234 doc.rs:15] Helpful for debugging
 //! // I20210401 12:34:56.000100  1234 doc.rs:16] An informational message
 //! ```
+//!
+//! ### Compile time filters
+//!
+//! The `max_level_*` and `release_max_level_*` features of the [`standard logging`] crate are
+//! honored as well. Levels removed at compile time are never logged, not even by [`vlog!`], and
+//! no log files are created for them.
+//!
+//! To remove verbose messages from the binary regardless of the level enable the `strip-vlog`
+//! feature. [`vlog!`] then expands to nothing, so neither the formatting code nor the format
+//! strings end up in the binary, and [`vlog_is_on!`] is always `false`.
 
 use std::{
-    cell::RefCell,
-    collections::HashMap,
+    cell::{Cell, OnceCell, RefCell, RefMut},
+    collections::{HashMap, VecDeque},
     convert::TryInto,
     ffi::{OsStr, OsString},
+    fmt,
     fs::{File, OpenOptions},
-    io::{LineWriter, Write},
+    io::{self, IoSlice, LineWriter, Write},
+    mem,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc,
+        Mutex,
+        OnceLock,
+        RwLock,
+        RwLockReadGuard,
+    },
+    time::{Duration, Instant},
 };
 
 use backtrace::Backtrace;
@@ -89,23 +109,337 @@ use bimap::BiMap;
 use chrono::{DateTime, Local};
 use if_empty::*;
 use log::{Level, Log, Metadata, Record};
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use termcolor::{Color, ColorSpec, StandardStream, WriteColor};
 use thread_local::ThreadLocal;
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "admin")]
+pub mod admin;
+#[cfg(feature = "archive")]
+pub mod archive;
+mod callsite;
+mod chain;
+mod context;
+mod duration;
+mod email;
+mod error;
+mod exit;
 mod flags;
+mod flusher;
+pub mod format;
+mod handle;
+mod header;
+mod hooks;
+mod index;
+mod journal;
+mod message;
+#[cfg(target_family = "unix")]
+mod mmap;
+mod panics;
+pub mod parse;
+mod pattern;
+pub mod prelude;
+mod queue;
+mod raw_files;
+mod sampling;
+mod sink;
+mod stats;
+mod sysresult;
+mod targets;
+mod trace;
+
+#[doc(hidden)]
+pub use callsite::__Callsite;
+pub use chain::ErrorChain;
+pub use context::{push_context, ContextGuard};
+pub use duration::{__duration_guard, DurationGuard};
+pub use email::{send_email, set_email_transport, EmailTransport};
+pub use error::GlogError;
+pub use flags::{Backpressure, ColorMode, Flags, PrefixId, SourcePath, TimestampClock, Timezone};
+#[cfg(feature = "flush-task")]
+pub use flusher::{periodic_flush, PeriodicFlush};
+pub use handle::{logger, InitGuard, LoggerHandle};
+pub use header::HeaderInfo;
+pub use hooks::{Destination, WriteEvent};
+pub use index::{read_log_index, IndexEntry};
+pub use journal::recover_journal;
+#[doc(hidden)]
+pub use log as __log;
+pub use message::LogMessage;
+pub use pattern::{MessagePattern, PatternError};
+pub use raw_files::flush_log_files_unsafe;
+pub use sink::{add_sink, RecordData, Sink};
+pub use stats::{LevelStats, LogStats};
+pub use sysresult::SysResult;
+use targets::{matches_target, TargetList};
+pub use trace::symbolicate;
+
+/// Messages longer than this are truncated by default, just like in glog
+const DEFAULT_MAX_MESSAGE_LENGTH: usize = 30000;
+const DEFAULT_DROPPED_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+static LOGGER: OnceLock<Glog> = OnceLock::new();
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+static ERROR_REPORTED: AtomicBool = AtomicBool::new(false);
+/// A writer added with [`add_writer`] and the formatter for its messages, the glog format if `None`
+type FormattedWriter = (Box<dyn Write + Send>, Option<Box<Formatter>>);
+
+static WRITERS: Mutex<Vec<FormattedWriter>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static OCCURRENCES: Cell<usize> = const { Cell::new(0) };
+    static VERBOSITY: Cell<i32> = const { Cell::new(0) };
+    // sequence number of the message currently being written
+    static SEQUENCE: Cell<u64> = const { Cell::new(0) };
+    // a fatal message is being logged, which passes the allowed targets
+    static LOGGING_FATAL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Callback invoked when writing a log message fails
+pub type ErrorHandler = dyn Fn(&GlogError) + Send + Sync;
+
+/// Callback rewriting each message before it is written
+pub type Redactor = dyn Fn(&str) -> String + Send + Sync;
+
+/// Callback building the log file header from the header information and the default header
+pub type HeaderBuilder = dyn Fn(&HeaderInfo, &str) -> String + Send + Sync;
+
+/// Callback deciding whether a message is written
+pub type RecordFilter = dyn Fn(&RecordData) -> bool + Send + Sync;
+
+/// Callback invoked for the first message of a level
+pub type FirstOccurrenceHook = dyn Fn(&RecordData) + Send + Sync;
+
+/// Callback formatting a message for a destination
+pub type Formatter = dyn Fn(&RecordData) -> String + Send + Sync;
+
+/// Callback invoked before or after a message is written to a destination
+pub type WriteHook = dyn Fn(&WriteEvent) + Send + Sync;
+
+/// Callback wrapping a newly created log file into another writer
+pub type FileWrapper = dyn Fn(File) -> io::Result<Box<dyn Write + Send>> + Send + Sync;
+
+/// Data buffered beyond this many bytes is written immediately
+const LOG_BUFFER_SIZE: usize = 64 * 1024;
+
+/// A record together with everything its destinations write, computed once per record
+struct Prepared<'a> {
+    record: &'a Record<'a>,
+    time: DateTime<Local>,
+    // time since glog has been created, for the monotonic timestamp and the uptime
+    elapsed: Duration,
+    text: String,
+    line: OnceCell<String>,
+    backtrace: OnceCell<Option<String>>,
+    data: OnceCell<RecordData>,
+}
+
+struct LogFile {
+    path: OsString,
+    file: LogWriter,
+    // buffered messages are coalesced into a single vectored write
+    buffer: Vec<Vec<u8>>,
+    buffered_bytes: usize,
+    last_flush: Instant,
+    // offset after the last message including the buffered ones
+    offset: u64,
+    index: Option<index::IndexWriter>,
+}
+
+impl LogFile {
+    fn new(path: OsString, file: LogWriter, offset: u64, index: Option<index::IndexWriter>) -> Self {
+        LogFile {
+            path,
+            file,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            last_flush: Instant::now(),
+            offset,
+            index,
+        }
+    }
+
+    /// Append `data` to the buffer and write the buffer to the file if `write_through` is set or it is full
+    fn write_buffered(&mut self, data: &[u8], write_through: bool) -> io::Result<()> {
+        self.offset += data.len() as u64;
+        if write_through && self.buffer.is_empty() {
+            self.last_flush = Instant::now();
+            return self.file.write_all(data);
+        }
+        self.buffer.push(data.to_vec());
+        self.buffered_bytes += data.len();
+        if write_through || self.buffered_bytes >= LOG_BUFFER_SIZE {
+            self.write_buffer()?;
+        }
+        Ok(())
+    }
+
+    fn write_buffer(&mut self) -> io::Result<()> {
+        self.last_flush = Instant::now();
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let mut slices = self.buffer.iter().map(|data| IoSlice::new(data)).collect::<Vec<_>>();
+        let mut slices = &mut slices[..];
+        let mut result = Ok(());
+        while !slices.is_empty() {
+            match self.file.write_vectored(slices) {
+                Ok(0) => {
+                    result = Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write buffered messages"));
+                    break;
+                }
+                Ok(written) => IoSlice::advance_slices(&mut slices, written),
+                Err(why) if why.kind() == io::ErrorKind::Interrupted => {}
+                Err(why) => {
+                    result = Err(why);
+                    break;
+                }
+            }
+        }
+        self.buffer.clear();
+        self.buffered_bytes = 0;
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer()?;
+        self.file.flush()
+    }
+}
+
+impl LogFile {
+    /// Close the file of the parent process in a forked child without writing to it
+    #[cfg(target_family = "unix")]
+    fn abandon(mut self) {
+        // the buffered messages are written by the parent
+        self.buffer.clear();
+        // truncating the mapped file or finishing the wrapped stream would corrupt the parent's file
+        if !matches!(self.file, LogWriter::File(_)) {
+            mem::forget(self);
+        }
+    }
+}
+
+impl Drop for LogFile {
+    fn drop(&mut self) {
+        let _ = self.write_buffer();
+    }
+}
+
+enum LogWriter {
+    File(File),
+    #[cfg(target_family = "unix")]
+    Mmap(mmap::MmapWriter),
+    Wrapped(Box<dyn Write + Send>, File),
+}
+
+impl LogWriter {
+    fn raw_file(&self) -> &File {
+        match self {
+            LogWriter::File(file) => file,
+            #[cfg(target_family = "unix")]
+            LogWriter::Mmap(writer) => writer.file(),
+            LogWriter::Wrapped(_, file) => file,
+        }
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            LogWriter::File(file) => file.write(buf),
+            #[cfg(target_family = "unix")]
+            LogWriter::Mmap(writer) => writer.write(buf),
+            LogWriter::Wrapped(writer, _) => writer.write(buf),
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        match self {
+            LogWriter::File(file) => file.write_vectored(bufs),
+            #[cfg(target_family = "unix")]
+            LogWriter::Mmap(writer) => writer.write_vectored(bufs),
+            LogWriter::Wrapped(writer, _) => writer.write_vectored(bufs),
+        }
+    }
 
-pub use flags::Flags;
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            LogWriter::File(file) => file.flush(),
+            #[cfg(target_family = "unix")]
+            LogWriter::Mmap(writer) => writer.flush(),
+            LogWriter::Wrapped(writer, _) => writer.flush(),
+        }
+    }
+}
 
 /// The logging structure doing all the heavy lifting
 pub struct Glog {
-    stderr_writer: ThreadLocal<RefCell<StandardStream>>,
+    // the color mode each thread's stream has been created with
+    stderr_writer: ThreadLocal<RefCell<(ColorMode, StandardStream)>>,
     compatible_verbosity: bool,
     compatible_date: bool,
-    flags: Flags,
+    verbosity_prefix: bool,
+    source_location: bool,
+    header_working_directory: bool,
+    source_path: SourcePath,
+    max_message_length: Option<usize>,
+    escape_control_characters: bool,
+    sequence_numbers: bool,
+    uptime: bool,
+    thread_id_width: usize,
+    mmap_files: bool,
+    append_log_files: bool,
+    close_on_exec: bool,
+    index_interval: Option<usize>,
+    cascade_log_files: bool,
+    crash_reports: bool,
+    log_panics: bool,
+    backtraces: trace::BacktraceOptions,
+    journal_size: Option<usize>,
+    journal: Option<Arc<Mutex<(OsString, journal::Journal)>>>,
+    recent_logs: Option<(usize, Arc<Mutex<VecDeque<String>>>)>,
+    email_throttle: Option<(Duration, Arc<Mutex<email::EmailDigest>>)>,
+    deterministic_output: bool,
+    standalone: bool,
+    flush_on_exit: bool,
+    describe_on_init: bool,
+    idle_flush: Option<Arc<flusher::IdleFlush>>,
+    sync_level: Option<Level>,
+    write_queue: Option<Arc<queue::WriteQueue>>,
+    dropped_report_interval: Duration,
+    flags: RwLock<Flags>,
     application_fingerprint: Option<String>,
+    program_name: Option<String>,
+    hostname: Option<String>,
+    username: Option<String>,
     start_time: DateTime<Local>,
-    file_writer: HashMap<Level, Arc<Mutex<RefCell<File>>>>,
+    start_instant: Instant,
+    timestamp_clock: TimestampClock,
+    file_writer: RwLock<HashMap<Level, Arc<Mutex<LogFile>>>>,
+    level_dirs: HashMap<Level, OsString>,
+    target_routes: Vec<(String, String)>,
+    level_remaps: Vec<(String, Level, Level)>,
+    blocked_targets: TargetList,
+    allowed_targets: TargetList,
+    sampling: HashMap<Level, u32>,
+    target_files: RwLock<HashMap<String, Arc<Mutex<LogFile>>>>,
     level_integers: BiMap<Level, i8>,
+    error_handler: Option<Arc<ErrorHandler>>,
+    redactor: Option<Arc<Redactor>>,
+    filter: Option<Arc<RecordFilter>>,
+    before_write: Option<Arc<WriteHook>>,
+    after_write: Option<Arc<WriteHook>>,
+    included_messages: Vec<MessagePattern>,
+    excluded_messages: Vec<MessagePattern>,
+    // the levels already seen are a bit set shared by all clones
+    first_occurrence: Option<(Level, Arc<FirstOccurrenceHook>, Arc<AtomicU8>)>,
+    file_wrapper: Option<Arc<FileWrapper>>,
+    header_builder: Option<Arc<HeaderBuilder>>,
+    messages_logged: AtomicU64,
+    stats: Arc<stats::Counters>,
 }
 
 impl Glog {
@@ -115,11 +449,64 @@ impl Glog {
             stderr_writer: ThreadLocal::new(),
             compatible_verbosity: true,
             compatible_date: true,
-            flags: Flags::default(),
+            verbosity_prefix: false,
+            source_location: true,
+            header_working_directory: false,
+            source_path: SourcePath::FileName,
+            max_message_length: Some(DEFAULT_MAX_MESSAGE_LENGTH),
+            escape_control_characters: false,
+            sequence_numbers: false,
+            uptime: false,
+            thread_id_width: 5,
+            mmap_files: false,
+            append_log_files: false,
+            close_on_exec: true,
+            index_interval: None,
+            cascade_log_files: true,
+            crash_reports: false,
+            log_panics: false,
+            backtraces: trace::BacktraceOptions::default(),
+            journal_size: None,
+            journal: None,
+            recent_logs: None,
+            email_throttle: None,
+            deterministic_output: false,
+            standalone: false,
+            flush_on_exit: false,
+            describe_on_init: false,
+            idle_flush: None,
+            sync_level: None,
+            write_queue: None,
+            dropped_report_interval: DEFAULT_DROPPED_REPORT_INTERVAL,
+            flags: RwLock::new(Flags::default()),
             application_fingerprint: None,
+            program_name: None,
+            hostname: None,
+            username: None,
             start_time: Local::now(),
-            file_writer: HashMap::new(),
+            start_instant: Instant::now(),
+            timestamp_clock: TimestampClock::WallClock,
+            file_writer: RwLock::new(HashMap::new()),
+            level_dirs: HashMap::new(),
+            target_routes: Vec::new(),
+            level_remaps: Vec::new(),
+            blocked_targets: TargetList::default(),
+            allowed_targets: TargetList::default(),
+            sampling: HashMap::new(),
+            target_files: RwLock::new(HashMap::new()),
             level_integers: BiMap::new(),
+            error_handler: None,
+            redactor: None,
+            filter: None,
+            before_write: None,
+            after_write: None,
+            included_messages: Vec::new(),
+            excluded_messages: Vec::new(),
+            first_occurrence: None,
+            file_wrapper: None,
+            header_builder: None,
+            messages_logged: AtomicU64::new(0),
+            stats: Arc::new(stats::Counters::default()),
         }
     }
 
@@ -136,20 +523,255 @@ impl Glog {
     ///
     /// info!("A log message");
     /// ```
-    pub fn init(&mut self, flags: Flags) -> Result<(), log::SetLoggerError> {
+    ///
+    /// # Errors
+    ///
+    /// Fails if the flags are invalid, the log files can't be created or another logger has
+    /// already been registered.
+    ///
+    /// ```
+    /// use std::ffi::OsString;
+    /// use glog::{Flags, GlogError};
+    ///
+    /// let result = glog::new().init(Flags {
+    ///     log_dir: OsString::from("/this/directory/does/not/exist/"),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// assert!(matches!(result, Err(GlogError::Io { .. })));
+    /// ```
+    pub fn init(&mut self, flags: Flags) -> Result<(), GlogError> {
+        self.prepare(flags)?;
+        let async_queue_size = self.flags().async_queue_size;
+        if async_queue_size > 0 && !self.flags().logtostderr {
+            self.write_queue = Some(Arc::new(queue::WriteQueue::new(async_queue_size)));
+        }
+        log::set_max_level(self.flags().minloglevel.to_level_filter());
+        let glog: &'static Glog = LOGGER.get_or_init(|| self.clone());
+        glog.spawn_writer()?;
+        log::set_logger(glog)?;
+        INITIALIZED.store(true, Ordering::SeqCst);
+        if self.flush_on_exit {
+            exit::install();
+        }
+        if self.log_panics {
+            panics::install();
+        }
+        if let Some(idle_flush) = &self.idle_flush {
+            idle_flush.clone().spawn();
+        }
+        Ok(())
+    }
+
+    /// Create the log files and return the logger without registering it
+    ///
+    /// Useful for applications which compose several loggers, e.g. with a multiplexer, or register
+    /// glog themselves with [`log::set_boxed_logger`]. The crate level functions like
+    /// [`flush`](crate::flush()) and [`logger`](crate::logger()) don't know about the returned logger,
+    /// so call its [`Log`] methods directly. Setting [`log::set_max_level`] is up to the caller.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::{vlog, Flags};
+    ///
+    /// let logger = glog::new().build_boxed(Flags {
+    ///         v: 1,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    /// log::set_max_level(LevelFilter::Info);
+    /// log::set_boxed_logger(logger).unwrap();
+    ///
+    /// info!("Logged by the boxed glog logger");
+    /// vlog!(1, "Logged as well");
+    /// vlog!(2, "Not logged");
+    /// log::logger().flush();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the flags are invalid or the log files can't be created.
+    pub fn build_boxed(mut self, flags: Flags) -> Result<Box<dyn Log>, GlogError> {
+        self.prepare(flags)?;
+        self.standalone = true;
+        Ok(Box::new(self))
+    }
+
+    fn prepare(&mut self, mut flags: Flags) -> Result<(), GlogError> {
+        flags.validate()?;
+        if flags.log_dir.is_empty() {
+            flags.log_dir = self.default_log_dir(&flags, true);
+        }
         self.level_integers.insert(Level::Trace, -2);
         self.level_integers.insert(Level::Debug, -1);
         self.level_integers.insert(Level::Info, 0);
         self.level_integers.insert(Level::Warn, 1);
         self.level_integers.insert(Level::Error, 2);
-        self.flags = flags;
-        if !self.flags.logtostderr {
-            self.create_log_files();
+        self.flags = RwLock::new(flags);
+        callsite::invalidate();
+        if !self.flags().logtostderr {
+            self.create_log_files()?;
+        }
+        if let Some(journal_size) = self.journal_size {
+            self.create_journal(journal_size)?;
+        }
+        if self.describe_on_init {
+            let flags = self.flags().clone();
+            eprint!("{}", self.describe(&flags));
+        }
+        Ok(())
+    }
+
+    fn default_log_dir(&self, flags: &Flags, create: bool) -> OsString {
+        let mut log_dir = match flags::platform_log_dir(&self.program_name()) {
+            // the directory is only needed if log files are written
+            Some(log_dir) if !flags.temp_log_dir && (!create || flags.logtostderr || std::fs::create_dir_all(&log_dir).is_ok()) => {
+                log_dir
+            }
+            _ => std::env::temp_dir(),
+        };
+        // the file names are appended to the directory
+        log_dir.push("");
+        log_dir.into_os_string()
+    }
+
+    fn is_active(&self) -> bool {
+        self.standalone || is_initialized()
+    }
+
+    /// Describe the configuration glog would use with `flags`
+    ///
+    /// Lists the active destinations, the effective log directory and log files, the filters and
+    /// all flags, so a misconfiguration is obvious before any message has been logged. After
+    /// initialization the log files show their actual paths, before only their name pattern.
+    /// Nothing is created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::Flags;
+    ///
+    /// let description = glog::new().describe(&Flags::default());
+    /// assert!(description.contains("destinations: files"));
+    /// println!("{}", description);
+    /// // glog configuration:
+    /// //   destinations: files
+    /// //   log directory: /var/log/server/
+    /// //   log files:
+    /// //     INFO: /var/log/server/server.localhost.nobody.log.INFO.<timestamp>.<pid>
+    /// // ...
+    /// ```
+    pub fn describe(&self, flags: &Flags) -> String {
+        let mut flags = flags.clone();
+        if flags.log_dir.is_empty() {
+            flags.log_dir = self.default_log_dir(&flags, false);
+        }
+        let mut destinations = Vec::new();
+        if flags.logtostderr || flags.alsologtostderr {
+            destinations.push("stderr");
+        }
+        if !flags.logtostderr {
+            destinations.push("files");
+        }
+        if flags.alsologtoemail.is_some() && flags.logemaillevel.is_some() {
+            destinations.push("email");
+        }
+        if self.journal_size.is_some() {
+            destinations.push("journal");
+        }
+
+        let mut description = String::from("glog configuration:\n");
+        description.push_str(&format!("  destinations: {}\n", destinations.join(", ")));
+        description.push_str(&format!("  log directory: {}\n", flags.log_dir.to_string_lossy()));
+        if !flags.logtostderr {
+            description.push_str("  log files:\n");
+            let file_writer = self.file_writer.read().unwrap();
+            for level in self.file_levels() {
+                let path = match file_writer.get(&level) {
+                    Some(file) => file.lock().unwrap().path.clone(),
+                    None => {
+                        let log_dir = self.level_dirs.get(&level).unwrap_or(&flags.log_dir);
+                        let mut path = self.log_file_base(log_dir, &level.to_string().to_uppercase());
+                        path.push(".<timestamp>.<pid>");
+                        path
+                    }
+                };
+                description.push_str(&format!(
+                    "    {}: {}\n",
+                    level.to_string().to_uppercase(),
+                    path.to_string_lossy()
+                ));
+            }
+            for (target, name) in &self.target_routes {
+                description.push_str(&format!("    {} (target {}): ", name, target));
+                match self.target_files.read().unwrap().get(name) {
+                    Some(file) => description.push_str(&file.lock().unwrap().path.to_string_lossy()),
+                    None => {
+                        description.push_str(&self.log_file_base(&flags.log_dir, name).to_string_lossy());
+                        description.push_str(".<timestamp>.<pid>");
+                    }
+                }
+                description.push('\n');
+            }
+        }
+        for (target, from, to) in &self.level_remaps {
+            description.push_str(&format!(
+                "  remap: {} {}->{}\n",
+                target,
+                from.to_string().to_uppercase(),
+                to.to_string().to_uppercase()
+            ));
+        }
+        if !self.allowed_targets.is_empty() {
+            description.push_str(&format!("  allowed targets: {}\n", self.allowed_targets.targets().join(", ")));
+        }
+        if !self.blocked_targets.is_empty() {
+            description.push_str(&format!("  blocked targets: {}\n", self.blocked_targets.targets().join(", ")));
+        }
+        let mut sampling = self.sampling.iter().collect::<Vec<_>>();
+        sampling.sort();
+        for (level, rate) in sampling {
+            description.push_str(&format!("  sampling: {} 1/{}\n", level.to_string().to_uppercase(), rate));
+        }
+        if self.filter.is_some() {
+            description.push_str("  filter: callback\n");
         }
-        // todo(#4): restore this once this can be changed during runtime for glog
-        // log::set_max_level(LevelFilter::Trace);
-        log::set_max_level(self.flags.minloglevel.to_level_filter());
-        log::set_boxed_logger(Box::new(self.clone()))
+        for pattern in &self.included_messages {
+            description.push_str(&format!("  include messages: {}\n", pattern));
+        }
+        for pattern in &self.excluded_messages {
+            description.push_str(&format!("  exclude messages: {}\n", pattern));
+        }
+        description.push_str(&format!("  flags: {:?}\n", flags));
+        description
+    }
+
+    /// Print the [`describe`](Glog::describe) output to stderr once glog has been initialized
+    ///
+    /// By default nothing is printed.
+    pub fn describe_on_init(mut self, describe_on_init: bool) -> Self {
+        self.describe_on_init = describe_on_init;
+        self
+    }
+
+    /// Initialize like [`init`](Glog::init) and return a guard which flushes all log destinations once it is dropped
+    ///
+    /// Useful for short-lived applications which would otherwise lose buffered messages when `main` returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// let _guard = glog::new().init_scoped(Flags::default()).unwrap();
+    ///
+    /// info!("Flushed when _guard goes out of scope");
+    /// ```
+    pub fn init_scoped(&mut self, flags: Flags) -> Result<InitGuard, GlogError> {
+        self.init(flags)?;
+        Ok(InitGuard::new())
     }
 
     /// Enable the year in the log timestamp
@@ -252,242 +874,2515 @@ impl Glog {
         self
     }
 
-    /// Set `fingerprint` as the application fingerprint in the log file header
-    pub fn set_application_fingerprint(mut self, fingerprint: &str) -> Self {
-        self.application_fingerprint = Some(fingerprint.to_owned());
+    /// Show the verbosity of [`vlog!`] messages in the prefix
+    ///
+    /// If enabled a message logged with verbosity `n` starts with `Vn` instead of `I`, which makes
+    /// it easy to grep for verbose logs.
+    ///
+    /// By default verbose logs use the `I` prefix like in glog.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::{vlog, Flags};
+    ///
+    /// glog::new()
+    ///     .verbosity_in_prefix(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         v: 2,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// vlog!(2, "A verbose message");
+    /// // V20401 12:34:56.987654   123 doc.rs:12] A verbose message
+    /// ```
+    pub fn verbosity_in_prefix(mut self, verbosity_prefix: bool) -> Self {
+        self.verbosity_prefix = verbosity_prefix;
         self
     }
 
-    fn match_level(&self, level: &Level) -> Level {
-        match level {
-            Level::Debug if self.compatible_verbosity => Level::Info,
-            Level::Trace if self.compatible_verbosity => Level::Info,
-            _ => *level,
-        }
-    }
-
-    fn create_log_files(&mut self) {
-        let log_file_dir = self.flags.log_dir.clone();
-        let mut log_file_name = OsString::new();
-        let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from_str("UNKNOWN").unwrap_or_default());
-        let exe = exe.file_name().unwrap_or_else(|| OsStr::new("UNKNOWN"));
-        log_file_name.push(exe);
-        log_file_name.push(".");
-        log_file_name.push(gethostname::gethostname().if_empty(OsString::from("(unknown)")));
-        log_file_name.push(".");
-        log_file_name.push(whoami::username().if_empty("invalid-user".to_string()));
-        log_file_name.push(".log.");
-
-        let log_file_suffix = format!(".{}.{}", Local::now().format("%Y%m%d-%H%M%S"), std::process::id());
-
-        let mut log_file_base = OsString::new();
-        log_file_base.push(&log_file_dir);
-        log_file_base.push(log_file_name);
-
-        let mut symlink_file_base = OsString::new();
-        symlink_file_base.push(log_file_dir);
-        symlink_file_base.push(exe);
-        symlink_file_base.push(".");
-        if !self.compatible_verbosity {
-            for level in &[Level::Trace, Level::Debug] {
-                let mut log_file_path = log_file_base.clone();
-                log_file_path.push(level.to_string().to_uppercase());
-                log_file_path.push(&log_file_suffix);
-                self.write_file_header(&log_file_path, level);
-                let mut symlink_file_name = symlink_file_base.clone();
-                symlink_file_name.push(level.to_string().to_uppercase());
-                self.create_symlink(&log_file_path, &symlink_file_name);
-            }
-        }
-        for level in &[Level::Info, Level::Warn, Level::Error] {
-            let mut log_file_path = log_file_base.clone();
-            log_file_path.push(level.to_string().to_uppercase());
-            log_file_path.push(&log_file_suffix);
-            self.write_file_header(&log_file_path, level);
-            let mut symlink_file_name = symlink_file_base.clone();
-            symlink_file_name.push(level.to_string().to_uppercase());
-            self.create_symlink(&log_file_path, &symlink_file_name);
-        }
-    }
-
-    fn write_file_header(&mut self, file_path: &OsString, level: &Level) {
-        {
-            let mut file = match File::create(file_path) {
-                Err(why) => panic!(
-                    "couldn't create {}: {}",
-                    file_path.to_str().unwrap_or("<INVALID FILE PATH>"),
-                    why
-                ),
-                Ok(file) => file,
-            };
-
-            let running_duration = Local::now() - self.start_time;
-
-            // todo(#3): integrate UTC
-            file.write_fmt(
-                format_args!("Log file created at:\n{}\nRunning on machine: {}\n{}Running duration (h:mm:ss): {}:{:02}:{:02}\nLog line format: [{}IWE]{}mmdd hh:mm:ss.uuuuuu threadid file:line] msg\n",
-                    Local::now().format("%Y/%m/%d %H:%M:%S"),
-                    gethostname::gethostname().to_str().unwrap_or("UNKNOWN"),
-                    if self.application_fingerprint.is_some() { format!("Application fingerprint: {}\n", self.application_fingerprint.clone().unwrap()) } else { String::new() },
-                    running_duration.num_hours(),
-                    running_duration.num_minutes(),
-                    running_duration.num_seconds(),
-                    if self.compatible_verbosity { "" } else { "TD" },
-                    if self.compatible_date { "" } else { "yyyy" },
-                )
-            ).expect("couldn't write log file header");
-
-            if let Err(why) = file.flush() {
-                panic!(
-                    "couldn't flush {} after writing file header: {}",
-                    file_path.to_str().unwrap(),
-                    why
-                )
-            }
-        }
-        self.file_writer.insert(
-            *level,
-            Arc::new(Mutex::new(RefCell::new(
-                OpenOptions::new()
-                    .append(true)
-                    .open(file_path)
-                    .expect("Couldn't open file after header is written"),
-            ))),
-        );
-    }
-
-    /// On supported platforms creates short stable named symlinks pointing to latest log file.
-    /// Example /tmp/main.INFO -> /tmp/main.hostname.username.log.INFO.<timestamp>
-    fn create_symlink(&self, long_name: &OsString, symlink_name: &OsString) {
-        #[cfg(target_family = "unix")]
-        {
-            // Unconditionally remove any existing symlink
-            let _ = std::fs::remove_file(symlink_name);
-            // Create new symlink
-            std::os::unix::fs::symlink(long_name, symlink_name)
-                .unwrap_or_else(|_| panic!("failed to create symlink {}", symlink_name.to_str().unwrap()));
-        }
+    /// Show the `file:line` of the log invocation in the prefix
+    ///
+    /// Disable this if source paths are considered sensitive in shipped binaries. The rest of the
+    /// prefix stays the same for all destinations.
+    ///
+    /// By default the source location is shown like in glog.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .with_source_location(false)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("No source location");
+    /// // I0401 12:34:56.987654   123] No source location
+    /// ```
+    pub fn with_source_location(mut self, source_location: bool) -> Self {
+        self.source_location = source_location;
+        self
     }
 
-    fn should_log_backtrace(&self, file_name: &str, line: u32) -> bool {
-        if self.flags.log_backtrace_at.is_some() {
-            format!("{file_name}:{line}") == *self.flags.log_backtrace_at.as_ref().unwrap()
-        } else {
-            false
-        }
+    /// Choose how the source file is shown in the `file:line` part of the prefix
+    ///
+    /// With only the file name, the default like in glog, files like `mod.rs` of different
+    /// modules are indistinguishable. [`log_backtrace_at`](Flags::log_backtrace_at) matches the
+    /// file name regardless of this setting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::{Flags, SourcePath};
+    ///
+    /// glog::new()
+    ///     .source_path(SourcePath::CrateRelative)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("With the path in the crate");
+    /// // I0401 12:34:56.987654   123 src/net/mod.rs:12] With the path in the crate
+    /// ```
+    pub fn source_path(mut self, source_path: SourcePath) -> Self {
+        self.source_path = source_path;
+        self
     }
 
-    fn record_to_file_name(record: &Record) -> String {
-        Path::new(record.file().unwrap_or(""))
-            .file_name()
-            .unwrap_or_default()
-            .to_os_string()
-            .into_string()
-            .unwrap_or_default()
+    /// Truncate messages longer than `max_message_length` bytes
+    ///
+    /// Truncated messages end with `...[truncated N bytes]`, so a single runaway message can't
+    /// blow up the log files. `None` disables the limit.
+    ///
+    /// By default messages are truncated after 30000 bytes like in glog.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .max_message_length(Some(10))
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("This message is too long");
+    /// // I0401 12:34:56.987654   123 doc.rs:12] This messa...[truncated 14 bytes]
+    /// ```
+    pub fn max_message_length(mut self, max_message_length: Option<usize>) -> Self {
+        self.max_message_length = max_message_length;
+        self
     }
 
-    fn build_log_message(&self, record: &Record) -> String {
-        format!(
-            "{}{} {:5} {}:{}] {}",
-            self.match_level(&record.metadata().level()).as_str().chars().next().unwrap(),
-            Local::now().format(&format!("{}%m%d %H:%M:%S%.6f", if self.compatible_date { "" } else { "%Y" })),
-            get_tid(),
-            Glog::record_to_file_name(record),
-            record.line().unwrap_or(0),
-            record.args(),
-        )
+    /// Escape line breaks and other control characters in messages
+    ///
+    /// Newlines, carriage returns and tabs become `\n`, `\r` and `\t`, any other control
+    /// character is written as `\u{..}`. This guarantees that every message occupies exactly one
+    /// line, as many log shippers require.
+    ///
+    /// By default messages are written as they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .escape_control_characters(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("first line\nsecond line");
+    /// // I0401 12:34:56.987654   123 doc.rs:12] first line\nsecond line
+    /// ```
+    pub fn escape_control_characters(mut self, escape_control_characters: bool) -> Self {
+        self.escape_control_characters = escape_control_characters;
+        self
     }
 
-    fn write_stderr(&self, record: &Record) {
-        let stderr_writer = self
-            .stderr_writer
-            .get_or(|| RefCell::new(StandardStream::stderr(ColorChoice::Auto)));
-        let stderr_writer = stderr_writer.borrow_mut();
-        let mut stderr_writer = LineWriter::new(stderr_writer.lock());
-
-        if self.flags.colorlogtostderr {
-            stderr_writer
-                .get_mut()
-                .set_color(ColorSpec::new().set_fg(match record.metadata().level() {
-                    Level::Error => Some(Color::Red),
-                    Level::Warn => Some(Color::Yellow),
-                    _ => None,
-                }))
-                .expect("failed to set color");
-        }
-
-        let file_name = Glog::record_to_file_name(record);
-
-        writeln!(stderr_writer, "{}", self.build_log_message(record)).expect("couldn't write log message");
+    /// Take the timestamps in the prefix from a monotonic clock instead of or in addition to the wall clock
+    ///
+    /// Monotonic timestamps count the nanoseconds since glog has been created and don't jump when
+    /// the system time is adjusted, which makes them suitable for latency analysis.
+    ///
+    /// By default the wall clock is used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::{Flags, TimestampClock};
+    ///
+    /// glog::new()
+    ///     .timestamp_clock(TimestampClock::Both)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("A log message");
+    /// // I0401 12:34:56.987654 1234567ns   123 doc.rs:12] A log message
+    /// ```
+    pub fn timestamp_clock(mut self, timestamp_clock: TimestampClock) -> Self {
+        self.timestamp_clock = timestamp_clock;
+        self
+    }
+
+    /// Write a sequence number after the thread and process id in the prefix
+    ///
+    /// The number starts at 1 and increases with every message of the process, so collectors can
+    /// detect dropped or reordered lines. A message written to several destinations has the same
+    /// number in all of them.
+    ///
+    /// By default no sequence number is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .sequence_numbers(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("first");
+    /// info!("second");
+    /// // I0401 12:34:56.987654   123 #1 doc.rs:12] first
+    /// // I0401 12:34:56.987700   123 #2 doc.rs:13] second
+    /// ```
+    pub fn sequence_numbers(mut self, sequence_numbers: bool) -> Self {
+        self.sequence_numbers = sequence_numbers;
+        self
+    }
+
+    /// Write the time since glog has been created after the thread and process id in the prefix
+    ///
+    /// The uptime is shown as `+h:mm:ss.uuuuuu`, which makes the sequence of events during the
+    /// startup easier to follow than absolute times.
+    ///
+    /// By default no uptime is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .uptime(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("Started");
+    /// // I0401 12:34:56.987654   123 +0:00:00.001234 doc.rs:12] Started
+    /// ```
+    pub fn uptime(mut self, uptime: bool) -> Self {
+        self.uptime = uptime;
+        self
+    }
+
+    /// Pad the thread and process id in the prefix to `width` characters
+    ///
+    /// Choose a width matching the thread ids of a platform to keep the columns aligned. A width
+    /// of 0 disables the padding.
+    ///
+    /// By default the thread id is padded to 5 characters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .thread_id_width(7)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("A log message");
+    /// // I0401 12:34:56.987654     123 doc.rs:12] A log message
+    /// ```
+    pub fn thread_id_width(mut self, width: usize) -> Self {
+        self.thread_id_width = width;
+        self
+    }
+
+    /// Write log files through memory mappings instead of write syscalls
+    ///
+    /// Intended for extremely high log rates. The files are extended in chunks of 1 MiB and
+    /// truncated to their real length when they are closed, so call [`shutdown`] before exiting.
+    /// Until then the files end with zero bytes.
+    ///
+    /// On platforms or file systems which don't support memory mappings normal writes are used.
+    ///
+    /// By default normal writes are used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().mmap_files(true).init(Flags::default()).unwrap();
+    ///
+    /// info!("Written through a memory mapping");
+    ///
+    /// glog::shutdown();
+    /// ```
+    pub fn mmap_files(mut self, mmap_files: bool) -> Self {
+        self.mmap_files = mmap_files;
+        self
+    }
+
+    /// Append to log files with a stable name instead of creating new timestamped files
+    ///
+    /// The files are named without timestamp and pid, e.g. `<program>.<hostname>.<user>.log.INFO`.
+    /// A new file gets the usual header, an existing file a `Log file restarted at ...` marker.
+    /// Useful for tools which run many times per minute and would litter the log directory
+    /// otherwise. Memory mapped files are disabled in this mode, as several processes may append to
+    /// the same file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().append_log_files(true).init(Flags::default()).unwrap();
+    ///
+    /// let path = glog::log_file_path(Level::Info).unwrap();
+    /// assert!(path.to_string_lossy().ends_with(".log.INFO"));
+    /// ```
+    pub fn append_log_files(mut self, append: bool) -> Self {
+        self.append_log_files = append;
+        self
+    }
+
+    /// Close the log files in processes started with `exec`
+    ///
+    /// Log files are opened with `O_CLOEXEC` so subprocesses don't inherit their descriptors,
+    /// e.g. keeping deleted files from being freed. Disable it for subprocesses which write to the
+    /// log files of their parent through the inherited descriptors. Has no effect on other
+    /// platforms than unix, where the files aren't inherited either.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::Flags;
+    ///
+    /// glog::new().close_log_files_on_exec(false).init(Flags::default()).unwrap();
+    /// ```
+    pub fn close_log_files_on_exec(mut self, close_on_exec: bool) -> Self {
+        self.close_on_exec = close_on_exec;
+        self
+    }
+
+    /// Write an index next to each log file with the time and offset of every `interval`th message
+    ///
+    /// The index is named like the log file with an `.idx` extension and can be read with
+    /// [`read_log_index`], so tools can seek into large log files by time instead of scanning
+    /// them. The offsets count the bytes glog wrote, so they don't match files changed by
+    /// [`wrap_log_files`](Glog::wrap_log_files) or appended to by several processes at once.
+    ///
+    /// By default no index is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{fs, io::{Read, Seek, SeekFrom}};
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().index_log_files(100).init(Flags::default()).unwrap();
+    ///
+    /// info!("Indexed");
+    /// glog::flush();
+    ///
+    /// let path = glog::log_file_path(Level::Info).unwrap();
+    /// let entries = glog::read_log_index(format!("{}.idx", path.display())).unwrap();
+    ///
+    /// let mut file = fs::File::open(path).unwrap();
+    /// file.seek(SeekFrom::Start(entries[0].offset)).unwrap();
+    /// let mut message = String::new();
+    /// file.read_to_string(&mut message).unwrap();
+    /// assert!(message.ends_with("] Indexed\n"));
+    /// ```
+    pub fn index_log_files(mut self, interval: usize) -> Self {
+        self.index_interval = Some(interval);
+        self
+    }
+
+    /// Write messages to the log files of less severe levels as well
+    ///
+    /// Like in glog a `WARN` message is written to the `WARN` and the `INFO` file by default.
+    /// Disable this to write each message only to the file of its own level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().cascade_log_files(false).init(Flags::default()).unwrap();
+    ///
+    /// warn!("Only written to the WARN file");
+    /// ```
+    pub fn cascade_log_files(mut self, cascade: bool) -> Self {
+        self.cascade_log_files = cascade;
+        self
+    }
+
+    /// Keep only a random sample of 1 in `rate` messages of `level`
+    ///
+    /// Meant for very hot paths where even [`log_every_n!`] isn't flexible enough. Kept messages
+    /// end with a `[sampled 1/rate]` marker, dropped ones count as suppressed in the
+    /// [`stats`](crate::stats()). A `rate` of 0 or 1 keeps every message.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .sample_level(Level::Info, 100)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// for request in 0..1000 {
+    ///     info!("handled request {}", request);
+    /// }
+    /// // I0401 12:34:56.987654   123 doc.rs:13] handled request 73 [sampled 1/100]
+    /// ```
+    pub fn sample_level(mut self, level: Level, rate: u32) -> Self {
+        self.sampling.insert(level, rate);
+        self
+    }
+
+    /// Resolve the symbols of the backtraces requested with [`log_backtrace_at`](Flags::log_backtrace_at)
+    ///
+    /// Resolving the function names and source locations takes much longer than capturing the
+    /// backtrace. Without resolving only the address of each frame is logged, relative to the
+    /// executable or library it belongs to, e.g. `3: 0x55d4c0a1b2c3 - /usr/bin/my_app+0x1b2c3`.
+    /// Turn them into symbols later with [`symbolicate`](crate::symbolicate()). Backtraces are
+    /// resolved by default. Crash reports are always resolved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .resolve_backtraces(false)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         log_backtrace_at: Some(format!("{}:{}", file!(), line!() + 5)),
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("Followed by the addresses of the frames");
+    /// ```
+    pub fn resolve_backtraces(mut self, resolve_backtraces: bool) -> Self {
+        self.backtraces.resolve = resolve_backtraces;
+        self
+    }
+
+    /// Only log the top `depth` frames of the backtraces requested with [`log_backtrace_at`](Flags::log_backtrace_at)
+    ///
+    /// Backtraces of deep stacks, e.g. in async runtimes, are mostly made up of frames which are
+    /// of no interest and overwhelm the log files. The omitted frames are replaced by a
+    /// `... N more frames` line. Resolved backtraces start at the log invocation, as the frames
+    /// of glog and the [`standard logging`] crate are skipped. By default all frames are logged.
+    ///
+    /// [`standard logging`]: https://crates.io/crates/log
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .backtrace_depth(2)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         log_backtrace_at: Some(format!("{}:{}", file!(), line!() + 5)),
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("Followed by the two innermost frames");
+    /// ```
+    pub fn backtrace_depth(mut self, depth: usize) -> Self {
+        self.backtraces.depth = Some(depth);
+        self
+    }
+
+    /// Log the backtraces requested with [`log_backtrace_at`](Flags::log_backtrace_at) in a short format
+    ///
+    /// Instead of a function name line followed by a source location line per symbol each frame
+    /// takes a single line, e.g. `3: server::handle at src/server.rs:42`. The frames of the
+    /// runtime calling `main` or the entry point of a thread are left out, like with
+    /// `RUST_BACKTRACE=1`. Has no effect on backtraces which aren't
+    /// [resolved](Glog::resolve_backtraces). Backtraces are logged in full by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .short_backtraces(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         log_backtrace_at: Some(format!("{}:{}", file!(), line!() + 5)),
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("Followed by one line per frame");
+    /// ```
+    pub fn short_backtraces(mut self, short_backtraces: bool) -> Self {
+        self.backtraces.short = short_backtraces;
+        self
+    }
+
+    /// Write a crash report file when [`fatal!`] or [`qfatal!`] is logged
+    ///
+    /// The report is written to `<log_dir>/<program>.crash.<timestamp>.<pid>` and contains the
+    /// fatal message, the process metadata of the log file headers, the messages kept with
+    /// [`keep_recent_logs`](Glog::keep_recent_logs) and a backtrace, so a postmortem doesn't need
+    /// to stitch together the files of all levels.
+    ///
+    /// # Example
+    ///
+    /// ```should_panic
+    /// use log::*;
+    /// use glog::{fatal, Flags};
+    ///
+    /// glog::new()
+    ///     .crash_reports(true)
+    ///     .keep_recent_logs(100)
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!("Part of the crash report");
+    /// fatal!("Can't continue");
+    /// ```
+    pub fn crash_reports(mut self, crash_reports: bool) -> Self {
+        self.crash_reports = crash_reports;
+        self
+    }
+
+    /// Log panics at ERROR with the name of the panicking thread and a backtrace
+    ///
+    /// Panics of worker threads usually go unnoticed as the default panic hook only prints them
+    /// to stderr. This installs a panic hook logging the panic message with the target `panic`,
+    /// so it ends up in the log files. The backtrace is rendered like the ones requested with
+    /// [`log_backtrace_at`](Flags::log_backtrace_at). Panics of [`fatal!`] are logged once.
+    ///
+    /// The panic hook installed before [`init`](Glog::init) is still called after the panic has
+    /// been logged, so test harnesses and other crates relying on it keep working. For the
+    /// default hook this means the panic is printed to stderr as well.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .log_panics(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// let worker = std::thread::Builder::new()
+    ///     .name("worker".to_owned())
+    ///     .spawn(|| panic!("Lost the connection"))
+    ///     .unwrap();
+    /// assert!(worker.join().is_err());
+    /// // E0401 12:34:56.987654   124 doc.rs:11] thread 'worker' panicked: Lost the connection
+    /// ```
+    pub fn log_panics(mut self, log_panics: bool) -> Self {
+        self.log_panics = log_panics;
+        self
+    }
+
+    /// Keep the last `size` bytes of log output in a crash durable journal
+    ///
+    /// The journal is a memory mapped file named `<program>.journal.<pid>` in the log directory.
+    /// Its content survives the process being killed, even if buffered messages never made it
+    /// into the log files, and can be read with [`recover_journal`].
+    ///
+    /// Journals need memory mappings and are only supported on unix platforms, elsewhere `init`
+    /// fails.
+    ///
+    /// By default no journal is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().with_journal(4096).init(Flags::default()).unwrap();
+    ///
+    /// info!("Kept in the journal");
+    ///
+    /// # #[cfg(target_family = "unix")]
+    /// # {
+    /// let tail = glog::recover_journal(glog::journal_path().unwrap()).unwrap();
+    /// assert!(tail.ends_with("] Kept in the journal\n"));
+    /// # }
+    /// ```
+    pub fn with_journal(mut self, size: usize) -> Self {
+        self.journal_size = Some(size);
+        self
+    }
+
+    /// Keep the last `count` messages in memory
+    ///
+    /// They can be retrieved with [`recent_logs`], e.g. to show the recent activity on a status
+    /// page without reading the log files.
+    ///
+    /// By default no messages are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().keep_recent_logs(2).init(Flags::default()).unwrap();
+    ///
+    /// info!("first");
+    /// info!("second");
+    /// info!("third");
+    ///
+    /// let recent_logs = glog::recent_logs();
+    /// assert_eq!(recent_logs.len(), 2);
+    /// assert!(recent_logs[0].ends_with("] second"));
+    /// assert!(recent_logs[1].ends_with("] third"));
+    /// ```
+    pub fn keep_recent_logs(mut self, count: usize) -> Self {
+        self.recent_logs = Some((count, Arc::new(Mutex::new(VecDeque::with_capacity(count)))));
+        self
+    }
+
+    /// Send at most one email per `interval` for messages matching [`alsologtoemail`](Flags::alsologtoemail)
+    ///
+    /// Messages logged within `interval` after an email has been sent are collected and sent as a
+    /// single digest with the next message once `interval` has passed, or by
+    /// [`flush`](crate::flush()) if no further message arrives. Up to 100 messages are kept in a
+    /// digest, it only counts the ones beyond.
+    ///
+    /// By default an email is sent for every message.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .throttle_emails(Duration::from_secs(60))
+    ///     .init(Flags {
+    ///         alsologtoemail: Some("oncall@example.com".to_owned()),
+    ///         logemaillevel: Some(Level::Error),
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///
+    /// error!("Sent right away");
+    /// error!("Sent in a digest a minute later");
+    /// ```
+    pub fn throttle_emails(mut self, interval: Duration) -> Self {
+        self.email_throttle = Some((interval, Arc::new(Mutex::new(email::EmailDigest::default()))));
+        self
+    }
+
+    /// Replace timestamps, thread and process ids with fixed placeholders
+    ///
+    /// This makes the output reproducible so it can be compared byte-for-byte in golden or
+    /// snapshot tests. The log file header uses the same placeholders for its creation time and
+    /// running duration.
+    ///
+    /// By default the output is not deterministic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .deterministic_output(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("A log message");
+    /// // I0000 00:00:00.000000     0 doc.rs:12] A log message
+    /// ```
+    ///
+    /// Comparing the output with a golden line:
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::{Flags, PrefixId};
+    /// use std::io::Write;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Buffer(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl Write for Buffer {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// glog::new()
+    ///     .deterministic_output(true)
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         log_prefix_id: PrefixId::Both,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// let buffer = Buffer::default();
+    /// glog::add_writer(Box::new(buffer.clone()));
+    ///
+    /// glog::log_at(Level::Info, "golden.rs", 7, format_args!("A log message"));
+    /// glog::flush();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+    ///     "I0000 00:00:00.000000     0     0 golden.rs:7] A log message\n"
+    /// );
+    /// ```
+    pub fn deterministic_output(mut self, deterministic: bool) -> Self {
+        self.deterministic_output = deterministic;
+        self
+    }
+
+    /// Write the working directory of the process into the log file headers
+    ///
+    /// The command line is always part of the header, so together with the working directory
+    /// anyone reading a log file can see exactly how the process was started.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::Flags;
+    ///
+    /// glog::new().header_working_directory(true).init(Flags::default()).unwrap();
+    ///
+    /// let header = glog::header_info().unwrap();
+    /// assert_eq!(header.working_directory, std::env::current_dir().ok());
+    /// assert!(!header.command_line.is_empty());
+    /// ```
+    pub fn header_working_directory(mut self, working_directory: bool) -> Self {
+        self.header_working_directory = working_directory;
+        self
+    }
+
+    /// Set `fingerprint` as the application fingerprint in the log file header
+    pub fn set_application_fingerprint(mut self, fingerprint: &str) -> Self {
+        self.application_fingerprint = Some(fingerprint.to_owned());
+        self
+    }
+
+    /// Use `program_name` instead of the name of the executable in log file names
+    pub fn set_program_name(mut self, program_name: &str) -> Self {
+        self.program_name = Some(program_name.to_owned());
+        self
+    }
+
+    /// Use `hostname` instead of the name of the machine in log file names and headers
+    ///
+    /// Useful in containers with meaningless hostnames.
+    pub fn set_hostname(mut self, hostname: &str) -> Self {
+        self.hostname = Some(hostname.to_owned());
+        self
+    }
+
+    /// Use `username` instead of the name of the current user in log file names
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .set_program_name("server")
+    ///     .set_hostname("localhost")
+    ///     .set_username("nobody")
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// // Log files are named like /tmp/server.localhost.nobody.log.INFO.20210401-123456.1234
+    /// ```
+    pub fn set_username(mut self, username: &str) -> Self {
+        self.username = Some(username.to_owned());
+        self
+    }
+
+    /// Install a handler for errors while writing log messages
+    ///
+    /// By default the first error is printed to stderr and all further errors are ignored, so an
+    /// IO error like a full disk or a closed pipe doesn't take down the application.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .with_error_handler(|error| eprintln!("logging failed: {}", error))
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!("A log message");
+    /// ```
+    pub fn with_error_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&GlogError) + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Install a callback which scrubs each message before it is written
+    ///
+    /// This allows to remove sensitive data like email addresses or tokens in a central place
+    /// instead of auditing every log statement. Only the message is passed to `redactor`, not the
+    /// prefix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .with_redactor(|message| message.replace("hunter2", "*******"))
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("The password is hunter2");
+    /// // I0401 12:34:56.987654   123 doc.rs:12] The password is *******
+    /// ```
+    pub fn with_redactor<F>(mut self, redactor: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.redactor = Some(Arc::new(redactor));
+        self
+    }
+
+    /// Only write messages for which `filter` returns `true`
+    ///
+    /// The filter sees the message with its full context after the level checks, which allows
+    /// bespoke suppression rules like per tenant filters. Suppressed messages count as such in the
+    /// [`stats`](crate::stats()).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .with_filter(|record| !record.key_values.iter().any(|(key, value)| key == "tenant" && value == "noisy"))
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!(tenant = "noisy"; "Not logged");
+    /// info!(tenant = "quiet"; "Logged");
+    /// ```
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&RecordData) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Only write messages whose text matches `pattern` or another included pattern
+    ///
+    /// The patterns are matched against the message text after redaction, without the prefix.
+    /// They are checked after the [filter](Glog::with_filter) and suppressed messages count as
+    /// such in the [`stats`](crate::stats()). By default every message is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::{Flags, MessagePattern};
+    ///
+    /// glog::new()
+    ///     .include_messages(MessagePattern::new(r"^request \d+").unwrap())
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("request 42 served in 3ms");
+    /// info!("Not logged");
+    /// ```
+    pub fn include_messages(mut self, pattern: MessagePattern) -> Self {
+        self.included_messages.push(pattern);
+        self
+    }
+
+    /// Drop messages whose text matches `pattern`
+    ///
+    /// Silences specific noisy lines which can't be changed at the source. Like the
+    /// [included](Glog::include_messages) patterns they are matched against the message text
+    /// after redaction and take precedence over them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::{Flags, MessagePattern};
+    ///
+    /// glog::new()
+    ///     .exclude_messages("(?i)connection reset by peer".parse().unwrap())
+    ///     .init(Flags {
+    ///         logtostderr: true,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// warn!("Connection reset by peer");
+    /// warn!("Disk almost full");
+    /// // W0401 12:34:56.987654   123 doc.rs:11] Disk almost full
+    /// ```
+    pub fn exclude_messages(mut self, pattern: MessagePattern) -> Self {
+        self.excluded_messages.push(pattern);
+        self
+    }
+
+    /// Call `hook` for the first message of each level at or above `threshold` in this process
+    ///
+    /// Useful to set a health flag or create a marker file once the first error occurs. The hook
+    /// runs after the message has been written to all destinations and may log itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// static HEALTHY: AtomicBool = AtomicBool::new(true);
+    ///
+    /// glog::new()
+    ///     .on_first_occurrence(Level::Error, |_| HEALTHY.store(false, Ordering::Relaxed))
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// warn!("Not severe enough");
+    /// assert!(HEALTHY.load(Ordering::Relaxed));
+    /// error!("Marks the process unhealthy");
+    /// assert!(!HEALTHY.load(Ordering::Relaxed));
+    /// ```
+    pub fn on_first_occurrence<F>(mut self, threshold: Level, hook: F) -> Self
+    where
+        F: Fn(&RecordData) + Send + Sync + 'static,
+    {
+        self.first_occurrence = Some((threshold, Arc::new(hook), Arc::new(AtomicU8::new(0))));
+        self
+    }
+
+    /// Call `hook` before a message is written to each of its destinations
+    ///
+    /// The [`WriteEvent`] tells the destination and how many bytes are written to it, which allows
+    /// audit trails of what has been logged where, custom metrics or, together with
+    /// [`after_write`](Glog::after_write), measuring the overhead of logging. Stderr, the log
+    /// files, the journal and the writers added with [`add_writer`] are reported. Messages for
+    /// the log files are reported by the writer thread if the
+    /// [asynchronous writer](Flags::async_queue_size) is used.
+    ///
+    /// The hooks may run while a destination is locked, so they must not log themselves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use log::*;
+    /// use glog::{Destination, Flags};
+    ///
+    /// static FILE_BYTES: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// glog::new()
+    ///     .before_write(|event| {
+    ///         if let Destination::File(_) = event.destination {
+    ///             FILE_BYTES.fetch_add(event.bytes, Ordering::Relaxed);
+    ///         }
+    ///     })
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!("Counted");
+    /// assert!(FILE_BYTES.load(Ordering::Relaxed) > 0);
+    /// ```
+    pub fn before_write<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&WriteEvent) + Send + Sync + 'static,
+    {
+        self.before_write = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `hook` after a message has been written to each of its destinations
+    ///
+    /// Gets the same events as [`before_write`](Glog::before_write), also if writing failed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{cell::Cell, time::{Duration, Instant}};
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// thread_local! {
+    ///     static STARTED: Cell<Option<Instant>> = Cell::new(None);
+    /// }
+    ///
+    /// glog::new()
+    ///     .before_write(|_| STARTED.with(|started| started.set(Some(Instant::now()))))
+    ///     .after_write(|event| {
+    ///         let took = STARTED.with(|started| started.take()).map(|started| started.elapsed());
+    ///         if took.unwrap_or_default() > Duration::from_millis(10) {
+    ///             eprintln!("writing {} bytes to {:?} took {:?}", event.bytes, event.destination, took);
+    ///         }
+    ///     })
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!("Timed");
+    /// ```
+    pub fn after_write<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&WriteEvent) + Send + Sync + 'static,
+    {
+        self.after_write = Some(Arc::new(hook));
+        self
+    }
+
+    /// Build the log file headers with `header_builder`
+    ///
+    /// The callback gets the [`HeaderInfo`] and the default glog compatible header and returns
+    /// the header to write, which allows to replace it or add lines like the git SHA of the build.
+    /// A missing line break at the end is added.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .with_file_header(|_, default_header| format!("{}Environment: staging\n", default_header))
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    /// ```
+    pub fn with_file_header<F>(mut self, header_builder: F) -> Self
+    where
+        F: Fn(&HeaderInfo, &str) -> String + Send + Sync + 'static,
+    {
+        self.header_builder = Some(Arc::new(header_builder));
+        self
+    }
+
+    /// Pass every log file through `wrapper` before anything is written to it
+    ///
+    /// The writer returned by `wrapper` receives all data including the header, which allows to
+    /// e.g. encrypt the log files at rest with the cipher of your choice. Takes precedence over
+    /// [`mmap_files`](Glog::mmap_files).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{fs::File, io::{self, Write}};
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// struct Encryptor<W: Write> {
+    ///     inner: W,
+    /// }
+    ///
+    /// impl<W: Write> Write for Encryptor<W> {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         // encrypt buf with the cipher of your choice before writing it
+    ///         self.inner.write(buf)
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         self.inner.flush()
+    ///     }
+    /// }
+    ///
+    /// glog::new()
+    ///     .wrap_log_files(|file: File| Ok(Box::new(Encryptor { inner: file })))
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!("Passed through the Encryptor");
+    /// ```
+    pub fn wrap_log_files<F>(mut self, wrapper: F) -> Self
+    where
+        F: Fn(File) -> io::Result<Box<dyn Write + Send>> + Send + Sync + 'static,
+    {
+        self.file_wrapper = Some(Arc::new(wrapper));
+        self
+    }
+
+    /// Flush all log destinations when the process exits
+    ///
+    /// On unix platforms an exit handler flushes everything when the process exits normally, and
+    /// handlers for `SIGINT` and `SIGTERM` sync the log files before the process terminates as it
+    /// would without them. Signal handlers installed by the application before [`init`](Glog::init)
+    /// are kept. Does nothing on other platforms.
+    ///
+    /// The signal handlers can only use [`flush_log_files_unsafe`], so messages still buffered
+    /// because of [`logbufsecs`](Flags::logbufsecs) or queued for the
+    /// [writer thread](Flags::async_queue_size) are lost on a signal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().flush_on_exit(true).init(Flags::default()).unwrap();
+    ///
+    /// info!("Flushed even if main returns without calling glog::flush");
+    /// ```
+    pub fn flush_on_exit(mut self, flush_on_exit: bool) -> Self {
+        self.flush_on_exit = flush_on_exit;
+        self
+    }
+
+    /// Store the log file of `level` in `dir` instead of [`log_dir`](Flags::log_dir)
+    ///
+    /// This allows to keep the important files on a persistent volume while the verbose ones go to
+    /// a faster or smaller one. The symlink to the file of `level` is created in `dir` as well.
+    /// Fatal messages are written to the `ERROR` file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::env::temp_dir;
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// let persistent = temp_dir().join("persistent");
+    /// std::fs::create_dir_all(&persistent).unwrap();
+    ///
+    /// glog::new().level_log_dir(Level::Error, &persistent).init(Flags::default()).unwrap();
+    ///
+    /// assert!(glog::log_file_path(Level::Error).unwrap().starts_with(&persistent));
+    /// let log_dir = glog::logger().unwrap().flags().log_dir;
+    /// assert!(glog::log_file_path(Level::Info).unwrap().starts_with(log_dir));
+    /// ```
+    pub fn level_log_dir<P: AsRef<Path>>(mut self, level: Level, dir: P) -> Self {
+        let mut dir = dir.as_ref().to_path_buf();
+        // the file names are appended to the directory
+        dir.push("");
+        self.level_dirs.insert(level, dir.into_os_string());
+        self
+    }
+
+    /// Flush the log files once no message has been buffered for `idle`
+    ///
+    /// With [`logbufsecs`](Flags::logbufsecs) messages are only flushed once the buffer interval
+    /// has passed, so the last ones before a pause may stay stale for seconds. A background thread
+    /// flushes them as soon as logging has been idle for `idle` instead. Only applies to the logger
+    /// registered with [`init`](Glog::init).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().flush_when_idle(Duration::from_millis(200)).init(Flags {
+    ///         logbufsecs: 30,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("Written to the file 200ms later instead of 30s");
+    /// ```
+    pub fn flush_when_idle(mut self, idle: Duration) -> Self {
+        self.idle_flush = Some(Arc::new(flusher::IdleFlush::new(idle)));
+        self
+    }
+
+    /// Write messages of `level` and more severe levels to disk before the logging call returns
+    ///
+    /// Messages more severe than [`logbuflevel`](Flags::logbuflevel) are written immediately,
+    /// but only reach the page cache of the operating system and get lost if the machine goes
+    /// down. Messages at `level` or above are synced to disk instead, after waiting for the
+    /// [asynchronous writer](Flags::async_queue_size) to write them. Syncing is slow, so only use
+    /// it for rare and important messages. By default nothing is synced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().sync_level(Level::Warn).init(Flags {
+    ///         logbufsecs: 30,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// info!("Buffered for up to 30s");
+    /// warn!("On disk once warn! returns");
+    /// ```
+    pub fn sync_level(mut self, level: Level) -> Self {
+        self.sync_level = Some(level);
+        self
+    }
+
+    /// Log how many messages have been dropped by a full writer queue at most every `interval`
+    ///
+    /// With [`async_queue_size`](Flags::async_queue_size) and a dropping
+    /// [`backpressure`](Flags::backpressure) policy a `WARN` message like
+    /// `120 messages dropped since last report (INFO: 118, WARN: 2)` is logged after messages
+    /// have been dropped, so the gap in the log files is visible. Pending drops are also
+    /// reported when glog is [flushed](crate::flush()). The drops are counted in the
+    /// [`stats`](crate::stats()) as well. By default they are reported at most every 10 seconds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use log::*;
+    /// use glog::{Backpressure, Flags};
+    ///
+    /// glog::new().report_dropped_messages(Duration::from_secs(60)).init(Flags {
+    ///         async_queue_size: 16,
+    ///         backpressure: Backpressure::Drop,
+    ///         ..Default::default()
+    ///     }).unwrap();
+    ///
+    /// for i in 0..1000 {
+    ///     info!("Message {}", i);
+    /// }
+    /// glog::flush();
+    ///
+    /// let dropped = glog::stats().level(Level::Info).dropped;
+    /// println!("{} messages were dropped", dropped);
+    /// ```
+    pub fn report_dropped_messages(mut self, interval: Duration) -> Self {
+        self.dropped_report_interval = interval;
+        self
+    }
+
+    /// Write the messages of `target` and its submodules to a dedicated log file instead of the severity files
+    ///
+    /// The file is named like the severity files with `name` in place of the level, e.g.
+    /// `<program>.<hostname>.<user>.log.audit.<timestamp>`. Messages whose target matches several
+    /// routes go to the file of the longest `target`. They are still written to stderr as usual.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .route_target("my_app::audit", "audit")
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!(target: "my_app::audit::login", "Only written to the audit file");
+    /// info!("Written to the INFO file");
+    /// ```
+    pub fn route_target(mut self, target: &str, name: &str) -> Self {
+        self.target_routes.push((target.to_owned(), name.to_owned()));
+        self
+    }
+
+    /// Log the messages of `target` and its submodules at `from` as `to` instead
+    ///
+    /// Tames dependencies which log at an inappropriate level, e.g. `hyper` warning about
+    /// things the application handles anyway. The remapped level is used for everything after
+    /// the rule applies: the [`minloglevel`](Flags::minloglevel), sampling, the filter, the
+    /// prefix and the log files. Rules of the longest matching `target` win and are applied
+    /// only once, so rules can't be chained. As the [`standard logging`] crate drops messages
+    /// below its [`max_level`](log::max_level) before glog sees them, only levels which are
+    /// logged at all can be promoted.
+    ///
+    /// [`standard logging`]: https://crates.io/crates/log
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .remap_level("hyper", Level::Warn, Level::Info)
+    ///     .init(Flags {
+    ///         minloglevel: Level::Warn,
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    ///
+    /// warn!(target: "hyper::proto", "Logged as INFO and therefore dropped");
+    /// warn!("Still a warning");
+    /// assert!(!log_enabled!(target: "hyper::proto", Level::Warn));
+    /// ```
+    pub fn remap_level(mut self, target: &str, from: Level, to: Level) -> Self {
+        self.level_remaps.push((target.to_owned(), from, to));
+        self
+    }
+
+    /// Drop all messages of `target` and its submodules regardless of their level
+    ///
+    /// Silences known noisy dependencies completely. Blocked messages count as suppressed in the
+    /// [`stats`](crate::stats()). Whether a target is blocked is cached, so the check is cheap even
+    /// with many blocked targets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .block_target("h2")
+    ///     .block_target("rustls::client")
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// error!(target: "h2::codec", "Not logged");
+    /// info!(target: "rustls::server", "Logged");
+    /// assert!(!log_enabled!(target: "h2", Level::Error));
+    /// ```
+    pub fn block_target(mut self, target: &str) -> Self {
+        self.blocked_targets.push(target);
+        self
+    }
+
+    /// Only log the messages of `target` and its submodules, together with the other allowed targets
+    ///
+    /// Once a target has been allowed the messages of all other targets are dropped, which helps
+    /// to instrument a single subsystem of an otherwise chatty application. This includes the
+    /// messages glog logs itself with the target `glog`. Blocked targets are dropped even if they
+    /// are submodules of an allowed target. By default every target is logged.
+    ///
+    /// Fatal messages and the panics logged with [`log_panics`](Glog::log_panics) are logged
+    /// regardless of their target.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new()
+    ///     .allow_target("my_app::storage")
+    ///     .block_target("my_app::storage::cache")
+    ///     .init(Flags::default())
+    ///     .unwrap();
+    ///
+    /// info!(target: "my_app::storage::disk", "Logged");
+    /// info!(target: "my_app::storage::cache", "Not logged");
+    /// error!(target: "my_app::network", "Not logged either");
+    /// assert!(!log_enabled!(target: "my_app::network", Level::Error));
+    /// ```
+    pub fn allow_target(mut self, target: &str) -> Self {
+        self.allowed_targets.push(target);
+        self
+    }
+
+    fn flags(&self) -> RwLockReadGuard<'_, Flags> {
+        self.flags.read().unwrap()
+    }
+
+    /// Change the flags with `change` and make the callsites decide again
+    fn update_flags<F: FnOnce(&mut Flags)>(&self, change: F) {
+        change(&mut self.flags.write().unwrap());
+        callsite::invalidate();
+    }
+
+    fn match_level(&self, level: &Level) -> Level {
+        match level {
+            Level::Debug if self.compatible_verbosity => Level::Info,
+            Level::Trace if self.compatible_verbosity => Level::Info,
+            _ => *level,
+        }
+    }
+
+    fn create_log_files(&self) -> Result<(), GlogError> {
+        let log_file_suffix = Glog::log_file_suffix();
+        for level in self.file_levels() {
+            let log_file = self.create_log_file(level, &log_file_suffix)?;
+            self.file_writer
+                .write()
+                .unwrap()
+                .insert(level, Arc::new(Mutex::new(log_file)));
+        }
+        for (_, name) in &self.target_routes {
+            if self.target_files.read().unwrap().contains_key(name) {
+                continue;
+            }
+            let log_file = self.open_log_file(&self.flags().log_dir.clone(), name, &log_file_suffix)?;
+            self.target_files
+                .write()
+                .unwrap()
+                .insert(name.clone(), Arc::new(Mutex::new(log_file)));
+        }
+        Ok(())
+    }
+
+    /// Start the thread writing the queued messages of the asynchronous writer
+    fn spawn_writer(&'static self) -> Result<(), GlogError> {
+        if let Some(write_queue) = &self.write_queue {
+            write_queue
+                .clone()
+                .spawn(move |message| {
+                    self.write_file_message(&message);
+                    self.report_dropped(false);
+                })
+                .map_err(|why| GlogError::io(&OsString::from("writer thread"), why))?;
+        }
+        Ok(())
+    }
+
+    /// Replace the files, journal and threads inherited from the parent process in a forked child
+    #[cfg(target_family = "unix")]
+    fn atfork_child(&'static self) {
+        if let Some(write_queue) = &self.write_queue {
+            write_queue.reset();
+            if let Err(why) = self.spawn_writer() {
+                self.handle_error(why);
+            }
+        }
+        if let Some(idle_flush) = &self.idle_flush {
+            idle_flush.clone().spawn();
+        }
+        let log_file_suffix = Glog::log_file_suffix();
+        for (level, file) in self.file_writer.read().unwrap().iter() {
+            match self.create_log_file(*level, &log_file_suffix) {
+                Ok(log_file) => mem::replace(&mut *file.lock().unwrap(), log_file).abandon(),
+                Err(why) => self.handle_error(why),
+            }
+        }
+        for (name, file) in self.target_files.read().unwrap().iter() {
+            match self.open_log_file(&self.flags().log_dir.clone(), name, &log_file_suffix) {
+                Ok(log_file) => mem::replace(&mut *file.lock().unwrap(), log_file).abandon(),
+                Err(why) => self.handle_error(why),
+            }
+        }
+        if let (Some(journal), Some(size)) = (&self.journal, self.journal_size) {
+            match self.open_journal(size) {
+                Ok(child_journal) => *journal.lock().unwrap() = child_journal,
+                Err(why) => self.handle_error(why),
+            }
+        }
+    }
+
+    fn target_file(&self, target: &str) -> Option<Arc<Mutex<LogFile>>> {
+        let (_, name) = self
+            .target_routes
+            .iter()
+            .filter(|(prefix, _)| matches_target(target, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())?;
+        self.target_files.read().unwrap().get(name).cloned()
+    }
+
+    /// The level `level` is remapped to for `target`, if a rule applies
+    fn remapped_level(&self, target: &str, level: Level) -> Option<Level> {
+        self.level_remaps
+            .iter()
+            .filter(|(prefix, from, _)| *from == level && matches_target(target, prefix))
+            .max_by_key(|(prefix, ..)| prefix.len())
+            .map(|(_, _, to)| *to)
+    }
+
+    fn disable_log_file(&self, level: Level) {
+        let level = self.match_level(&level);
+        if let Some(file) = self.file_writer.write().unwrap().remove(&level) {
+            raw_files::unregister(level);
+            let mut log_file = file.lock().unwrap();
+            if let Err(why) = log_file.flush() {
+                self.handle_error(GlogError::io(&log_file.path, why));
+            }
+        }
+    }
+
+    fn set_logtostderr(&self, logtostderr: bool) -> Result<(), GlogError> {
+        self.open_files_for(logtostderr)?;
+        self.update_flags(|flags| flags.logtostderr = logtostderr);
+        if logtostderr {
+            // the files stay open for switching back and get everything buffered until now
+            self.flush();
+        }
+        Ok(())
+    }
+
+    /// Replace the flags at once, nothing is changed if the log files can't be created
+    #[cfg(feature = "admin")]
+    fn set_flags(&self, flags: Flags) -> Result<(), GlogError> {
+        self.open_files_for(flags.logtostderr)?;
+        let (logtostderr, minloglevel) = (flags.logtostderr, flags.minloglevel);
+        self.update_flags(|current| *current = flags);
+        log::set_max_level(minloglevel.to_level_filter());
+        if logtostderr {
+            self.flush();
+        }
+        Ok(())
+    }
+
+    /// Create the log files if the process started on stderr and switches to them
+    fn open_files_for(&self, logtostderr: bool) -> Result<(), GlogError> {
+        let started_on_stderr = self.file_writer.read().unwrap().is_empty() && self.target_files.read().unwrap().is_empty();
+        if !logtostderr && started_on_stderr && self.is_active() {
+            // the default directory is only created at init if log files are written
+            let log_dir = self.flags().log_dir.clone();
+            std::fs::create_dir_all(&log_dir).map_err(|why| GlogError::io(&log_dir, why))?;
+            self.create_log_files()?;
+        }
+        Ok(())
+    }
+
+    fn enable_log_file(&self, level: Level) -> Result<(), GlogError> {
+        let level = self.match_level(&level);
+        if self.flags().logtostderr || !self.is_active() {
+            return Ok(());
+        }
+        let mut files = self.file_writer.write().unwrap();
+        if files.contains_key(&level) {
+            return Ok(());
+        }
+        let log_file = self.create_log_file(level, &Glog::log_file_suffix())?;
+        files.insert(level, Arc::new(Mutex::new(log_file)));
+        Ok(())
+    }
+
+    fn log_writer(&self, file: File, file_path: &OsString) -> io::Result<LogWriter> {
+        if let Some(wrapper) = &self.file_wrapper {
+            let raw_file = file.try_clone()?;
+            return Ok(LogWriter::Wrapped(wrapper(file)?, raw_file));
+        }
+        // other processes may append to the same file at the same time
+        if self.mmap_files && !self.append_log_files {
+            if let Some(writer) = Glog::mmap_writer(file_path) {
+                return Ok(writer);
+            }
+        }
+        Ok(LogWriter::File(file))
+    }
+
+    #[cfg(target_family = "unix")]
+    fn mmap_writer(file_path: &OsString) -> Option<LogWriter> {
+        // fall back to normal writes if the file system doesn't support mapping the file
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(file_path)
+            .and_then(mmap::MmapWriter::new)
+            .map(LogWriter::Mmap)
+            .ok()
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    fn mmap_writer(_file_path: &OsString) -> Option<LogWriter> {
+        None
+    }
+
+    fn create_journal(&mut self, size: usize) -> Result<(), GlogError> {
+        let journal = self.open_journal(size)?;
+        self.journal = Some(Arc::new(Mutex::new(journal)));
+        Ok(())
+    }
+
+    fn open_journal(&self, size: usize) -> Result<(OsString, journal::Journal), GlogError> {
+        let mut journal_path = self.flags().log_dir.clone();
+        journal_path.push(self.program_name());
+        journal_path.push(format!(".journal.{}", std::process::id()));
+        let journal = journal::Journal::create(&journal_path, size).map_err(|why| GlogError::io(&journal_path, why))?;
+        Ok((journal_path, journal))
+    }
+
+    fn write_journal(&self, prepared: &Prepared) {
+        if let Some(journal) = &self.journal {
+            let message = format!("{}\n", self.log_line(prepared));
+            let event = WriteEvent {
+                level: prepared.record.level(),
+                destination: Destination::Journal,
+                bytes: message.len(),
+            };
+            let mut journal = journal.lock().unwrap();
+            self.notify_write(&self.before_write, &event);
+            journal.1.append(message.as_bytes());
+            self.notify_write(&self.after_write, &event);
+        }
+    }
+
+    /// Write the fatal message together with everything useful for a postmortem into a dedicated file
+    fn write_crash_report(&self, target: &str, file: &str, line: u32, args: fmt::Arguments) {
+        let mut path = self.flags().log_dir.clone();
+        path.push(self.program_name());
+        path.push(".crash");
+        path.push(Glog::log_file_suffix());
+        let header = self.header_info();
+        let mut report = format!(
+            "Crash report of {} (pid {}) on {}\nCommand line: {}\nCrashed at: {}\nRunning since: {}\nThread: {} ({})\nFatal message: {}:{}] {}\n",
+            self.program_name().to_string_lossy(),
+            header.pid,
+            header.hostname,
+            header.command_line.join(" "),
+            self.format_time(Local::now(), "%Y/%m/%d %H:%M:%S%.6f"),
+            self.format_time(header.start_time, "%Y/%m/%d %H:%M:%S"),
+            std::thread::current().name().unwrap_or("unnamed"),
+            self.thread_id(),
+            file,
+            line,
+            args,
+        );
+        if let Some(fingerprint) = &header.application_fingerprint {
+            report.push_str(&format!("Application fingerprint: {}\n", fingerprint));
+        }
+        if let Some((_, recent_logs)) = &self.recent_logs {
+            report.push_str("\nRecent messages:\n");
+            for message in recent_logs.lock().unwrap().iter() {
+                report.push_str(message);
+                report.push('\n');
+            }
+        }
+        report.push_str(&format!("\nBacktrace:\n{:?}\n", Backtrace::new()));
+        match std::fs::write(&path, report) {
+            Ok(()) => __log_at(
+                Level::Error,
+                target,
+                file,
+                line,
+                format_args!("Crash report written to {}", path.to_string_lossy()),
+            ),
+            Err(why) => self.handle_error(GlogError::io(&path, why)),
+        }
+    }
+
+    fn remember_recent_log(&self, prepared: &Prepared) {
+        if let Some((count, recent_logs)) = &self.recent_logs {
+            if *count == 0 {
+                return;
+            }
+            let message = self.log_line(prepared).to_owned();
+            let mut recent_logs = recent_logs.lock().unwrap();
+            if recent_logs.len() == *count {
+                recent_logs.pop_front();
+            }
+            recent_logs.push_back(message);
+        }
+    }
+
+    fn file_levels(&self) -> Vec<Level> {
+        let mut levels = Vec::new();
+        if !self.compatible_verbosity {
+            levels.extend_from_slice(&[Level::Trace, Level::Debug]);
+        }
+        levels.extend_from_slice(&[Level::Info, Level::Warn, Level::Error]);
+        // levels removed at compile time are never written
+        levels.retain(|level| *level <= log::STATIC_MAX_LEVEL);
+        levels
+    }
+
+    fn log_file_suffix() -> String {
+        format!(".{}.{}", Local::now().format("%Y%m%d-%H%M%S"), std::process::id())
+    }
+
+    fn create_log_file(&self, level: Level, log_file_suffix: &str) -> Result<LogFile, GlogError> {
+        let log_file = self.open_log_file(&self.log_dir_of(level), &level.to_string().to_uppercase(), log_file_suffix)?;
+        raw_files::register(level, log_file.file.raw_file());
+        Ok(log_file)
+    }
+
+    /// Directory of the log files of `level`
+    fn log_dir_of(&self, level: Level) -> OsString {
+        match self.level_dirs.get(&level) {
+            Some(log_dir) => log_dir.clone(),
+            None => self.flags().log_dir.clone(),
+        }
+    }
+
+    /// Path of the log files for `name` in `log_dir` without the timestamp and pid
+    fn log_file_base(&self, log_dir: &OsStr, name: &str) -> OsString {
+        let mut log_file_base = OsString::new();
+        log_file_base.push(log_dir);
+        log_file_base.push(self.program_name());
+        log_file_base.push(".");
+        log_file_base.push(self.hostname());
+        log_file_base.push(".");
+        log_file_base.push(
+            self.username
+                .clone()
+                .unwrap_or_else(whoami::username)
+                .if_empty("invalid-user".to_string()),
+        );
+        log_file_base.push(".log.");
+        log_file_base.push(name);
+        log_file_base
+    }
+
+    fn open_log_file(&self, log_file_dir: &OsStr, name: &str, log_file_suffix: &str) -> Result<LogFile, GlogError> {
+        let exe = self.program_name();
+
+        let (log_file_path, file, header, offset) = if self.append_log_files {
+            let log_file_path = self.log_file_base(log_file_dir, name);
+            let file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(&log_file_path)
+                .map_err(|why| GlogError::io(&log_file_path, why))?;
+            let len = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            let header = if len > 0 { self.restart_marker() } else { self.file_header() };
+            (log_file_path, file, header, len)
+        } else {
+            let mut log_file_path = self.log_file_base(log_file_dir, name);
+            log_file_path.push(log_file_suffix);
+            // files recreated within the same second get a sequence number instead of replacing the previous file,
+            // create_new makes sure another process or glog instance never gets the same one
+            let mut sequence = 0;
+            let file = loop {
+                match OpenOptions::new().write(true).create_new(true).open(&log_file_path) {
+                    Ok(file) => break file,
+                    Err(why) if why.kind() == io::ErrorKind::AlreadyExists => {
+                        sequence += 1;
+                        log_file_path = self.log_file_base(log_file_dir, name);
+                        log_file_path.push(format!("{}.{}", log_file_suffix, sequence));
+                    }
+                    Err(why) => return Err(GlogError::io(&log_file_path, why)),
+                }
+            };
+            (log_file_path, file, self.file_header(), 0)
+        };
+
+        #[cfg(target_family = "unix")]
+        if !self.close_on_exec {
+            inherit_on_exec(&file).map_err(|why| GlogError::io(&log_file_path, why))?;
+        }
+
+        let mut file = self
+            .log_writer(file, &log_file_path)
+            .map_err(|why| GlogError::io(&log_file_path, why))?;
+        file.write_all(header.as_bytes())
+            .and_then(|_| file.flush())
+            .map_err(|why| GlogError::io(&log_file_path, why))?;
+
+        let mut symlink_file_name = OsString::new();
+        symlink_file_name.push(log_file_dir);
+        symlink_file_name.push(exe);
+        symlink_file_name.push(".");
+        symlink_file_name.push(name);
+        self.create_symlink(&log_file_path, &symlink_file_name)?;
+
+        let index = match self.index_interval {
+            Some(interval) => Some(
+                index::IndexWriter::create(&log_file_path, interval, self.append_log_files)
+                    .map_err(|why| GlogError::io(&log_file_path, why))?,
+            ),
+            None => None,
+        };
+
+        let offset = offset + header.len() as u64;
+        Ok(LogFile::new(log_file_path, file, offset, index))
+    }
+
+    fn program_name(&self) -> OsString {
+        match &self.program_name {
+            Some(program_name) => OsString::from(program_name),
+            None => {
+                let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from_str("UNKNOWN").unwrap_or_default());
+                exe.file_name().unwrap_or_else(|| OsStr::new("UNKNOWN")).to_os_string()
+            }
+        }
+    }
+
+    fn hostname(&self) -> OsString {
+        match &self.hostname {
+            Some(hostname) => OsString::from(hostname),
+            None => gethostname::gethostname(),
+        }
+        .if_empty(OsString::from("(unknown)"))
+    }
+
+    fn header_info(&self) -> HeaderInfo {
+        HeaderInfo {
+            start_time: self.start_time,
+            hostname: self.hostname().to_str().unwrap_or("UNKNOWN").to_owned(),
+            application_fingerprint: self.application_fingerprint.clone(),
+            pid: std::process::id(),
+            command_line: std::env::args_os().map(|arg| arg.to_string_lossy().into_owned()).collect(),
+            working_directory: if self.header_working_directory {
+                std::env::current_dir().ok()
+            } else {
+                None
+            },
+        }
+    }
+
+    fn file_header(&self) -> String {
+        let header = self.header_info();
+        let default_header = self.default_file_header(&header);
+        match &self.header_builder {
+            Some(header_builder) => {
+                let mut custom_header = header_builder(&header, &default_header);
+                if !custom_header.is_empty() && !custom_header.ends_with('\n') {
+                    custom_header.push('\n');
+                }
+                custom_header
+            }
+            None => default_header,
+        }
+    }
+
+    fn restart_marker(&self) -> String {
+        let restarted_at = if self.deterministic_output {
+            "0000/00/00 00:00:00".to_owned()
+        } else {
+            self.format_time(Local::now(), "%Y/%m/%d %H:%M:%S")
+        };
+        format!("Log file restarted at: {} by pid {}\n", restarted_at, std::process::id())
+    }
+
+    fn default_file_header(&self, header: &HeaderInfo) -> String {
+        let running_duration = if self.deterministic_output {
+            chrono::Duration::zero()
+        } else {
+            Local::now() - self.start_time
+        };
+        let created_at = if self.deterministic_output {
+            "0000/00/00 00:00:00".to_owned()
+        } else {
+            self.format_time(Local::now(), "%Y/%m/%d %H:%M:%S")
+        };
+
+        let command_line = header
+            .command_line
+            .iter()
+            .map(|arg| {
+                if arg.contains(char::is_whitespace) {
+                    format!("{:?}", arg)
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let working_directory = match &header.working_directory {
+            Some(working_directory) => format!("Working directory: {}\n", working_directory.display()),
+            None => String::new(),
+        };
+        format!("Log file created at:\n{}\nRunning on machine: {}\nCommand line: {}\n{}{}Running duration (h:mm:ss): {}:{:02}:{:02}\nLog line format: [{}IWE]{} {}{}{}{}] msg\n",
+            created_at,
+            header.hostname,
+            command_line,
+            working_directory,
+            match &header.application_fingerprint { Some(fingerprint) => format!("Application fingerprint: {}\n", fingerprint), None => String::new() },
+            running_duration.num_hours(),
+            running_duration.num_minutes(),
+            running_duration.num_seconds(),
+            if self.compatible_verbosity { "" } else { "TD" },
+            match self.timestamp_clock {
+                TimestampClock::WallClock => format!("{}mmdd hh:mm:ss.uuuuuu", if self.compatible_date { "" } else { "yyyy" }),
+                TimestampClock::Monotonic => "nnnns".to_owned(),
+                TimestampClock::Both =>
+                    format!("{}mmdd hh:mm:ss.uuuuuu nnnns", if self.compatible_date { "" } else { "yyyy" }),
+            },
+            self.flags().log_prefix_id.format_name(),
+            if self.uptime { " +h:mm:ss.uuuuuu" } else { "" },
+            if self.sequence_numbers { " #sequence" } else { "" },
+            if self.source_location { " file:line" } else { "" },
+        )
+    }
+
+    /// On supported platforms creates short stable named symlinks pointing to latest log file.
+    /// Example /tmp/main.INFO -> /tmp/main.hostname.username.log.INFO.<timestamp>
+    fn create_symlink(&self, long_name: &OsString, symlink_name: &OsString) -> Result<(), GlogError> {
+        #[cfg(target_family = "unix")]
+        {
+            // Unconditionally remove any existing symlink
+            let _ = std::fs::remove_file(symlink_name);
+            // Create new symlink
+            std::os::unix::fs::symlink(long_name, symlink_name).map_err(|why| GlogError::io(symlink_name, why))?;
+        }
+        Ok(())
+    }
+
+    fn should_log_backtrace(&self, record: &Record) -> bool {
+        match &self.flags().log_backtrace_at {
+            Some(log_backtrace_at) => {
+                flags::matches_backtrace_at(log_backtrace_at, record.file().unwrap_or(""), record.line().unwrap_or(0))
+            }
+            None => false,
+        }
+    }
+
+    fn escape_control(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_control() => escaped.extend(c.escape_unicode()),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    fn timestamp(&self, prepared: &Prepared) -> String {
+        match self.timestamp_clock {
+            TimestampClock::WallClock => self.wall_clock_timestamp(prepared.time),
+            TimestampClock::Monotonic => self.monotonic_timestamp(prepared.elapsed),
+            TimestampClock::Both => format!(
+                "{} {}",
+                self.wall_clock_timestamp(prepared.time),
+                self.monotonic_timestamp(prepared.elapsed)
+            ),
+        }
+    }
+
+    fn wall_clock_timestamp(&self, time: DateTime<Local>) -> String {
+        if self.deterministic_output {
+            format!("{}0000 00:00:00.000000", if self.compatible_date { "" } else { "0000" })
+        } else {
+            self.format_time(
+                time,
+                &format!("{}%m%d %H:%M:%S%.6f", if self.compatible_date { "" } else { "%Y" }),
+            )
+        }
+    }
+
+    fn format_time(&self, time: DateTime<Local>, format: &str) -> String {
+        self.flags().log_timezone.format(time, format)
+    }
+
+    fn monotonic_timestamp(&self, elapsed: Duration) -> String {
+        if self.deterministic_output {
+            "0ns".to_owned()
+        } else {
+            format!("{}ns", elapsed.as_nanos())
+        }
+    }
+
+    fn thread_id(&self) -> u64 {
+        if self.deterministic_output {
+            0
+        } else {
+            get_tid()
+        }
+    }
+
+    fn process_id(&self) -> u32 {
+        if self.deterministic_output {
+            0
+        } else {
+            std::process::id()
+        }
+    }
+
+    fn prefix_id(&self) -> String {
+        let width = self.thread_id_width;
+        match self.flags().log_prefix_id {
+            PrefixId::ThreadId => format!("{:>width$}", self.thread_id(), width = width),
+            PrefixId::ProcessId => format!("{:>width$}", self.process_id(), width = width),
+            PrefixId::Both => format!("{:>width$} {:>width$}", self.process_id(), self.thread_id(), width = width),
+        }
+    }
+
+    fn level_prefix(&self, record: &Record) -> String {
+        let verbosity = VERBOSITY.with(Cell::get);
+        if self.verbosity_prefix && verbosity > 0 {
+            format!("V{}", verbosity)
+        } else {
+            self.match_level(&record.metadata().level()).as_str()[..1].to_owned()
+        }
+    }
+
+    fn message_text(&self, record: &Record) -> String {
+        let mut text = format!("{}{}", context::prefix(), record.args());
+        if let Some(rate) = self.sampling.get(&record.level()).filter(|rate| **rate > 1) {
+            text.push_str(&format!(" [sampled 1/{}]", rate));
+        }
+        if let Some(redactor) = &self.redactor {
+            text = redactor(&text);
+        }
+        if self.escape_control_characters && text.contains(char::is_control) {
+            text = Glog::escape_control(&text);
+        }
+        if let Some(max_message_length) = self.max_message_length {
+            if text.len() > max_message_length {
+                let mut end = max_message_length;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let truncated = text.len() - end;
+                text.truncate(end);
+                text.push_str(&format!("...[truncated {} bytes]", truncated));
+            }
+        }
+        text
+    }
+
+    /// Prepare `record` for its destinations, which all get the same text and timestamp
+    fn prepare_record<'a>(&self, record: &'a Record<'a>) -> Prepared<'a> {
+        Prepared {
+            record,
+            time: Local::now(),
+            elapsed: self.start_instant.elapsed(),
+            text: self.message_text(record),
+            line: OnceCell::new(),
+            backtrace: OnceCell::new(),
+            data: OnceCell::new(),
+        }
+    }
+
+    /// The message with its prefix as written to stderr and the log files
+    fn log_line<'p>(&self, prepared: &'p Prepared) -> &'p str {
+        prepared.line.get_or_init(|| {
+            format!(
+                "{}{} {}{}{}] {}",
+                self.level_prefix(prepared.record),
+                self.timestamp(prepared),
+                self.prefix_id(),
+                self.prefix_columns(prepared.elapsed),
+                self.source_location(prepared.record),
+                prepared.text,
+            )
+        })
+    }
+
+    /// The backtrace logged with the message, if its location is one of `log_backtrace_at`
+    fn backtrace<'p>(&self, prepared: &'p Prepared) -> Option<&'p str> {
+        prepared
+            .backtrace
+            .get_or_init(|| {
+                if self.should_log_backtrace(prepared.record) {
+                    Some(trace::capture(self.backtraces))
+                } else {
+                    None
+                }
+            })
+            .as_deref()
+    }
+
+    /// The message as passed to sinks, filters and hooks
+    fn record_data<'p>(&self, prepared: &'p Prepared) -> &'p RecordData {
+        prepared
+            .data
+            .get_or_init(|| RecordData::new(prepared.record, self.thread_id(), prepared.text.clone(), prepared.time))
+    }
+
+    /// The optional columns between the ids and the source location
+    fn prefix_columns(&self, elapsed: Duration) -> String {
+        let mut columns = String::new();
+        if self.uptime {
+            let uptime = if self.deterministic_output { Duration::ZERO } else { elapsed };
+            let seconds = uptime.as_secs();
+            columns.push_str(&format!(
+                " +{}:{:02}:{:02}.{:06}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60,
+                uptime.subsec_micros()
+            ));
+        }
+        if self.sequence_numbers {
+            columns.push_str(&format!(" #{}", SEQUENCE.with(Cell::get)));
+        }
+        columns
+    }
+
+    fn source_location(&self, record: &Record) -> String {
+        if self.source_location {
+            format!(
+                " {}:{}",
+                self.source_path.format(record.file().unwrap_or("")),
+                record.line().unwrap_or(0)
+            )
+        } else {
+            String::new()
+        }
+    }
+
+    fn handle_error(&self, error: GlogError) {
+        self.stats.write_error();
+        match &self.error_handler {
+            Some(handler) => handler(&error),
+            None => {
+                if !ERROR_REPORTED.swap(true, Ordering::SeqCst) {
+                    eprintln!("glog: {} (further errors will not be reported)", error);
+                }
+            }
+        }
+    }
+
+    fn write_stderr(&self, prepared: &Prepared) -> io::Result<()> {
+        let message = self.log_line(prepared);
+        let backtrace = self.backtrace(prepared);
+        let event = WriteEvent {
+            level: prepared.record.level(),
+            destination: Destination::Stderr,
+            bytes: message.len() + 1 + backtrace.map_or(0, |backtrace| backtrace.len() + 1),
+        };
+        self.notify_write(&self.before_write, &event);
+        let result = self.write_stderr_message(prepared.record.level(), message, backtrace);
+        self.notify_write(&self.after_write, &event);
+        result
+    }
+
+    fn notify_write(&self, hook: &Option<Arc<WriteHook>>, event: &WriteEvent) {
+        if let Some(hook) = hook {
+            hook(event);
+        }
+    }
+
+    /// The stderr stream of the calling thread, created again if the color mode has changed
+    fn stderr_stream(&self) -> RefMut<'_, StandardStream> {
+        let stderr_color = self.flags().stderr_color;
+        let stream = || (stderr_color, StandardStream::stderr(stderr_color.color_choice()));
+        let mut cached = self.stderr_writer.get_or(|| RefCell::new(stream())).borrow_mut();
+        if cached.0 != stderr_color {
+            *cached = stream();
+        }
+        RefMut::map(cached, |(_, stream)| stream)
+    }
+
+    fn write_stderr_message(&self, level: Level, message: &str, backtrace: Option<&str>) -> io::Result<()> {
+        let stderr_writer = self.stderr_stream();
+        // std converts to UTF-16 for WriteConsoleW on Windows consoles, which needs whole characters
+        // per write, and the line writer only splits the output at line breaks
+        let mut stderr_writer = LineWriter::new(stderr_writer.lock());
+
+        if self.flags().colorlogtostderr {
+            stderr_writer.get_mut().set_color(ColorSpec::new().set_fg(match level {
+                Level::Error => Some(Color::Red),
+                Level::Warn => Some(Color::Yellow),
+                _ => None,
+            }))?;
+        }
+
+        writeln!(stderr_writer, "{}", message)?;
 
-        if self.flags.colorlogtostderr {
-            stderr_writer.get_mut().reset().expect("failed to reset color");
+        if self.flags().colorlogtostderr {
+            stderr_writer.get_mut().reset()?;
         }
 
-        if self.should_log_backtrace(&file_name, record.line().unwrap_or(0)) {
-            writeln!(stderr_writer, "{:?}", Backtrace::new()).expect("Couldn't write backtrace");
+        if let Some(backtrace) = backtrace {
+            writeln!(stderr_writer, "{}", backtrace)?;
         }
+        Ok(())
     }
 
     fn level_as_int(&self, level: &Level) -> i8 {
         *self.level_integers.get_by_left(&self.match_level(level)).unwrap()
     }
 
-    fn write_file(&self, record: &Record) {
+    fn write_file(&self, prepared: &Prepared) {
+        // everything depending on the logging thread is resolved before the message is queued
+        let message = queue::FileMessage {
+            level: prepared.record.level(),
+            target: prepared.record.target().to_owned(),
+            message: format!("{}\n", self.log_line(prepared)),
+            backtrace: self.backtrace(prepared).map(|backtrace| format!("{}\n", backtrace)),
+        };
+        match &self.write_queue {
+            Some(write_queue) if !write_queue.is_writer() => {
+                let sync = self.is_synced(message.level);
+                match write_queue.push(message, self.flags().backpressure) {
+                    Ok(Some(dropped)) => self.stats.dropped(dropped.level),
+                    Ok(None) => {}
+                    // closed by a shutdown
+                    Err(message) => return self.write_file_message(&message),
+                }
+                if sync {
+                    write_queue.drain();
+                }
+            }
+            _ => self.write_file_message(&message),
+        }
+    }
+
+    fn write_file_message(&self, message: &queue::FileMessage) {
+        if let Some(file) = self.target_file(&message.target) {
+            self.write_log_file(&file, message.level, &message.message, true);
+            return;
+        }
+        let files = self.file_writer.read().unwrap();
         // prevent writing to non existing writer if minloglevel is <INFO
-        for level_int in self.level_as_int(&self.flags.minloglevel)..=self.level_as_int(&record.level()) {
+        let lowest_level = if self.cascade_log_files {
+            self.level_as_int(&self.flags().minloglevel)
+        } else {
+            self.level_as_int(&message.level)
+        };
+        for level_int in lowest_level..=self.level_as_int(&message.level) {
             let level = self.level_integers.get_by_right(&level_int).unwrap();
-            let file_write_guard = self.file_writer.get(level).unwrap().lock().unwrap();
-            let mut file_writer = (*file_write_guard).borrow_mut();
-            if let Err(why) = file_writer.write_fmt(format_args!("{}\n", self.build_log_message(record))) {
-                panic!("couldn't write log message to file for level {}: {}", record.level(), why)
+            // files are gone once glog has been shut down
+            let file = match files.get(level) {
+                Some(file) => file,
+                None => continue,
+            };
+            self.write_log_file(file, message.level, &message.message, true);
+        }
+
+        if let Some(backtrace) = &message.backtrace {
+            let level = self.match_level(&self.flags().minloglevel);
+            if let Some(file) = files.get(&level) {
+                // continues the message, so the index doesn't get an entry for it
+                self.write_log_file(file, message.level, backtrace, false);
             }
         }
+    }
 
-        if self.should_log_backtrace(&Glog::record_to_file_name(record), record.line().unwrap_or(0)) {
-            let level = self.match_level(&self.flags.minloglevel);
-            let file_write_guard = self.file_writer.get(&level).unwrap().lock().unwrap();
-            let mut file_writer = (*file_write_guard).borrow_mut();
-            if let Err(why) = file_writer.write_fmt(format_args!("{:?}\n", Backtrace::new())) {
-                panic!("couldn't write backtrace to {} file: {}", level, why)
+    /// Write `data` to `file`, `starts_record` unless it continues the previous write of a message
+    fn write_log_file(&self, file: &Mutex<LogFile>, level: Level, data: &str, starts_record: bool) {
+        let mut log_file = file.lock().unwrap();
+        let log_file = &mut *log_file;
+        if let Some(index) = log_file.index.as_mut().filter(|_| starts_record) {
+            if let Err(why) = index.record(log_file.offset) {
+                self.handle_error(GlogError::io(index.path(), why));
+            }
+        }
+        let sync = self.is_synced(level);
+        let write_through = {
+            let flags = self.flags();
+            sync || flags.logbufsecs == 0
+                || level < flags.logbuflevel
+                || log_file.last_flush.elapsed() >= Duration::from_secs(flags.logbufsecs.into())
+        };
+        fn event(path: &OsStr, level: Level, bytes: usize) -> WriteEvent<'_> {
+            WriteEvent {
+                level,
+                destination: Destination::File(path),
+                bytes,
+            }
+        }
+        self.notify_write(&self.before_write, &event(&log_file.path, level, data.len()));
+        match log_file.write_buffered(data.as_bytes(), write_through) {
+            Ok(()) => self.stats.written(level, data.len()),
+            Err(why) => self.handle_error(GlogError::io(&log_file.path, why)),
+        }
+        if sync {
+            if let Err(why) = log_file.flush().and_then(|_| log_file.file.raw_file().sync_data()) {
+                self.handle_error(GlogError::io(&log_file.path, why));
+            }
+        }
+        self.notify_write(&self.after_write, &event(&log_file.path, level, data.len()));
+        if !write_through {
+            if let Some(idle_flush) = &self.idle_flush {
+                idle_flush.buffered();
             }
         }
     }
 
-    fn write_sinks(&self) {}
-}
+    /// Whether messages of `level` are synced to disk, see [`sync_level`](Glog::sync_level)
+    fn is_synced(&self, level: Level) -> bool {
+        self.sync_level.is_some_and(|sync_level| level <= sync_level)
+    }
 
-impl Log for Glog {
-    fn enabled(&self, metadata: &Metadata) -> bool {
-        self.flags.minloglevel >= metadata.level()
+    /// Log how many messages have been dropped by the writer queue since the last report
+    fn report_dropped(&self, force: bool) {
+        let write_queue = match &self.write_queue {
+            Some(write_queue) => write_queue,
+            None => return,
+        };
+        let levels = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+        let stats = self.stats.snapshot();
+        let dropped = levels.map(|level| stats.level(level).dropped);
+        let since = match write_queue.due_report(dropped, self.dropped_report_interval, force) {
+            Some(since) => since,
+            None => return,
+        };
+        let per_level = levels
+            .iter()
+            .zip(since.iter())
+            .filter(|(_, dropped)| **dropped > 0)
+            .map(|(level, dropped)| format!("{}: {}", level.to_string().to_uppercase(), dropped))
+            .collect::<Vec<_>>();
+        self.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("glog")
+                .file_static(Some(file!()))
+                .line(Some(line!()))
+                .args(format_args!(
+                    "{} messages dropped since last report ({})",
+                    since.iter().sum::<u64>(),
+                    per_level.join(", ")
+                ))
+                .build(),
+        );
     }
 
-    fn log(&self, record: &Record) {
-        if !self.enabled(record.metadata()) {
+    fn write_email(&self, prepared: &Prepared) {
+        let record = prepared.record;
+        let to = {
+            let flags = self.flags();
+            match (&flags.alsologtoemail, flags.logemaillevel) {
+                (Some(to), Some(level)) if record.level() <= level => to.clone(),
+                _ => return,
+            }
+        };
+        let message = self.log_line(prepared).to_owned();
+        match &self.email_throttle {
+            Some((interval, digest)) => {
+                // release the lock before sending so a transport which logs can't deadlock
+                let due = digest.lock().unwrap().add(*interval, record.level(), message);
+                if let Some(due) = due {
+                    self.send_digest(&to, due);
+                }
+            }
+            None => self.send_log_email(&to, record.level(), &message),
+        }
+    }
+
+    fn flush_email_digest(&self) {
+        let (interval, digest) = match &self.email_throttle {
+            Some(email_throttle) => email_throttle,
+            None => return,
+        };
+        let to = match &self.flags().alsologtoemail {
+            Some(to) => to.clone(),
+            None => return,
+        };
+        let due = digest.lock().unwrap().take_due(*interval);
+        if let Some(due) = due {
+            self.send_digest(&to, due);
+        }
+    }
+
+    fn send_digest(&self, to: &str, due: email::DueDigest) {
+        let mut body = due.messages.join("\n");
+        if due.omitted > 0 {
+            body.push_str(&format!("\n... and {} more messages", due.omitted));
+        }
+        self.send_log_email(to, due.level, &body);
+    }
+
+    fn send_log_email(&self, to: &str, level: Level, body: &str) {
+        let subject = format!(
+            "[LOG] {}: {}",
+            self.match_level(&level).as_str(),
+            self.program_name().to_string_lossy()
+        );
+        if let Err(why) = send_email(to, &subject, body) {
+            self.handle_error(GlogError::io(&OsString::from(format!("email to {}", to)), why));
+        }
+    }
+
+    fn notify_first_occurrence(&self, prepared: &Prepared) {
+        if let Some((threshold, hook, seen)) = &self.first_occurrence {
+            let level = prepared.record.level();
+            if level > *threshold {
+                return;
+            }
+            let bit = 1 << level as u8;
+            if seen.fetch_or(bit, Ordering::Relaxed) & bit == 0 {
+                hook(self.record_data(prepared));
+            }
+        }
+    }
+
+    fn write_writers(&self, prepared: &Prepared) {
+        let mut writers = WRITERS.lock().unwrap();
+        if writers.is_empty() {
+            return;
+        }
+        for (index, (writer, formatter)) in writers.iter_mut().enumerate() {
+            let formatted = match formatter {
+                Some(formatter) => formatter(self.record_data(prepared)),
+                None => self.log_line(prepared).to_owned(),
+            };
+            let event = WriteEvent {
+                level: prepared.record.level(),
+                destination: Destination::Writer(index),
+                bytes: formatted.len() + 1,
+            };
+            self.notify_write(&self.before_write, &event);
+            let result = writeln!(writer, "{}", formatted);
+            self.notify_write(&self.after_write, &event);
+            if let Err(why) = result {
+                self.handle_error(GlogError::io(&OsString::from("writer"), why));
+            }
+        }
+    }
+
+    fn write_sinks(&self, prepared: &Prepared) {
+        if !sink::has_sinks() {
+            return;
+        }
+        sink::write(self.record_data(prepared));
+    }
+
+    fn flush_files(&self, min_level: Level) {
+        for (level, file) in self.file_writer.read().unwrap().iter() {
+            if *level > min_level {
+                continue;
+            }
+            let mut log_file = file.lock().unwrap();
+            if let Err(why) = log_file.flush() {
+                self.handle_error(GlogError::io(&log_file.path, why));
+            }
+        }
+    }
+
+    fn flush_target_files(&self) {
+        for file in self.target_files.read().unwrap().values() {
+            let mut log_file = file.lock().unwrap();
+            if let Err(why) = log_file.flush() {
+                self.handle_error(GlogError::io(&log_file.path, why));
+            }
+        }
+    }
+
+    fn record_enabled(&self, target: &str, level: Level) -> bool {
+        level <= log::STATIC_MAX_LEVEL
+            && level <= log::max_level()
+            && self.flags().minloglevel >= level
+            && (self.allowed_targets.is_empty()
+                || self.allowed_targets.contains(target)
+                || target == panics::TARGET
+                || LOGGING_FATAL.with(Cell::get))
+            && !self.blocked_targets.contains(target)
+    }
+
+    fn log_record(&self, record: &Record) {
+        // vlog! checks the verbosity itself unless glog is installed by the application
+        if !self.record_enabled(record.target(), record.level()) || VERBOSITY.with(Cell::get) > self.flags().v {
+            self.stats.suppressed(record.level());
             return;
         }
+        if let Some(rate) = self.sampling.get(&record.level()) {
+            if !sampling::keep(*rate) {
+                self.stats.suppressed(record.level());
+                return;
+            }
+        }
+        let prepared = self.prepare_record(record);
+        if let Some(filter) = &self.filter {
+            if !filter(self.record_data(&prepared)) {
+                self.stats.suppressed(record.level());
+                return;
+            }
+        }
+        if !self.included_messages.is_empty() || !self.excluded_messages.is_empty() {
+            let text = &prepared.text;
+            let included = self.included_messages.is_empty() || self.included_messages.iter().any(|pattern| pattern.is_match(text));
+            if !included || self.excluded_messages.iter().any(|pattern| pattern.is_match(text)) {
+                self.stats.suppressed(record.level());
+                return;
+            }
+        }
 
-        if self.flags.logtostderr || self.flags.alsologtostderr {
-            self.write_stderr(record);
+        let (logtostderr, alsologtostderr) = {
+            let flags = self.flags();
+            (flags.logtostderr, flags.alsologtostderr)
+        };
+        let sequence = self.messages_logged.fetch_add(1, Ordering::Relaxed) + 1;
+        // restored afterwards in case a destination logs itself
+        let previous_sequence = SEQUENCE.with(|current| current.replace(sequence));
+        self.stats.emitted(record.level());
+        // after a shutdown messages only go to stderr just like before initialization in glog
+        let initialized = self.is_active();
+        if logtostderr || alsologtostderr || !initialized {
+            if let Err(why) = self.write_stderr(&prepared) {
+                self.handle_error(GlogError::io(&OsString::from("stderr"), why));
+            }
         }
-        if !self.flags.logtostderr {
-            self.write_file(record);
+        if !logtostderr && initialized {
+            self.write_file(&prepared);
+        }
+        self.write_journal(&prepared);
+        self.remember_recent_log(&prepared);
+        self.write_email(&prepared);
+        self.write_writers(&prepared);
+        self.write_sinks(&prepared);
+        self.notify_first_occurrence(&prepared);
+        SEQUENCE.with(|current| current.set(previous_sequence));
+    }
+}
+
+impl Log for Glog {
+    // every filter deciding whether a message is written belongs here so `log_enabled!`,
+    // `vlog_is_on!` and `LogMessage` agree with the actual output
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let level = self
+            .remapped_level(metadata.target(), metadata.level())
+            .unwrap_or(metadata.level());
+        self.record_enabled(metadata.target(), level)
+    }
+
+    fn log(&self, record: &Record) {
+        match self.remapped_level(record.target(), record.level()) {
+            Some(level) => self.log_record(&record.to_builder().level(level).build()),
+            None => self.log_record(record),
         }
-        self.write_sinks();
     }
 
     fn flush(&self) {
-        let stderr_writer = self
-            .stderr_writer
-            .get_or(|| RefCell::new(StandardStream::stderr(ColorChoice::Auto)));
-        let mut stderr_writer = stderr_writer.borrow_mut();
-        stderr_writer.flush().ok();
+        self.stderr_stream().flush().ok();
+
+        for (writer, _) in WRITERS.lock().unwrap().iter_mut() {
+            if let Err(why) = writer.flush() {
+                self.handle_error(GlogError::io(&OsString::from("writer"), why));
+            }
+        }
 
-        for file in self.file_writer.values() {
-            let file_guard = file.lock().unwrap();
-            let mut file_writer = (*file_guard).borrow_mut();
-            file_writer.flush().expect("couldn't sync log to disk");
+        if let Some(write_queue) = &self.write_queue {
+            // queued before draining so the report is written as well
+            self.report_dropped(true);
+            write_queue.drain();
         }
+        self.flush_files(Level::Trace);
+        self.flush_target_files();
+        self.flush_email_digest();
+        sink::flush();
     }
 }
 
@@ -501,6 +3396,72 @@ fn get_tid() -> u64 {
     nix::unistd::gettid().as_raw().try_into().unwrap()
 }
 
+#[cfg(target_os = "freebsd")]
+fn get_tid() -> u64 {
+    extern "C" {
+        fn pthread_getthreadid_np() -> nix::libc::c_int;
+    }
+    // SAFETY: returns the id of the calling thread and can't fail
+    unsafe { pthread_getthreadid_np() }.try_into().unwrap()
+}
+
+#[cfg(target_os = "openbsd")]
+fn get_tid() -> u64 {
+    extern "C" {
+        fn getthrid() -> nix::libc::pid_t;
+    }
+    // SAFETY: returns the id of the calling thread and can't fail
+    unsafe { getthrid() }.try_into().unwrap()
+}
+
+#[cfg(target_os = "netbsd")]
+fn get_tid() -> u64 {
+    extern "C" {
+        fn _lwp_self() -> nix::libc::c_int;
+    }
+    // SAFETY: returns the light weight process id of the calling thread and can't fail
+    unsafe { _lwp_self() }.try_into().unwrap()
+}
+
+#[cfg(target_os = "dragonfly")]
+fn get_tid() -> u64 {
+    extern "C" {
+        fn lwp_gettid() -> nix::libc::c_int;
+    }
+    // SAFETY: returns the light weight process id of the calling thread and can't fail
+    unsafe { lwp_gettid() }.try_into().unwrap()
+}
+
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+fn get_tid() -> u64 {
+    extern "C" {
+        fn thr_self() -> nix::libc::c_uint;
+    }
+    // SAFETY: returns the id of the calling thread and can't fail
+    unsafe { thr_self() }.into()
+}
+
+/// Process local ids for targets without native thread ids, which stay the same for each thread
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "windows"
+)))]
+fn get_tid() -> u64 {
+    static NEXT_TID: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static TID: u64 = NEXT_TID.fetch_add(1, Ordering::Relaxed);
+    }
+    TID.with(|tid| *tid)
+}
+
 #[cfg(target_os = "windows")]
 mod bindings {
     windows::include_bindings!();
@@ -515,15 +3476,135 @@ impl Clone for Glog {
     fn clone(&self) -> Glog {
         Glog {
             stderr_writer: ThreadLocal::new(),
-            flags: self.flags.clone(),
+            flags: RwLock::new(self.flags().clone()),
             application_fingerprint: self.application_fingerprint.clone(),
-            file_writer: self.file_writer.clone(),
+            program_name: self.program_name.clone(),
+            hostname: self.hostname.clone(),
+            username: self.username.clone(),
+            file_writer: RwLock::new(self.file_writer.read().unwrap().clone()),
+            level_dirs: self.level_dirs.clone(),
+            target_routes: self.target_routes.clone(),
+            level_remaps: self.level_remaps.clone(),
+            blocked_targets: self.blocked_targets.clone(),
+            allowed_targets: self.allowed_targets.clone(),
+            sampling: self.sampling.clone(),
+            target_files: RwLock::new(self.target_files.read().unwrap().clone()),
             level_integers: self.level_integers.clone(),
+            error_handler: self.error_handler.clone(),
+            redactor: self.redactor.clone(),
+            filter: self.filter.clone(),
+            before_write: self.before_write.clone(),
+            after_write: self.after_write.clone(),
+            included_messages: self.included_messages.clone(),
+            excluded_messages: self.excluded_messages.clone(),
+            first_occurrence: self.first_occurrence.clone(),
+            file_wrapper: self.file_wrapper.clone(),
+            header_builder: self.header_builder.clone(),
+            journal: self.journal.clone(),
+            recent_logs: self.recent_logs.clone(),
+            email_throttle: self.email_throttle.clone(),
+            idle_flush: self.idle_flush.clone(),
+            write_queue: self.write_queue.clone(),
+            messages_logged: AtomicU64::new(self.messages_logged.load(Ordering::Relaxed)),
+            stats: self.stats.clone(),
             ..*self
         }
     }
 }
 
+impl fmt::Debug for Glog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = self.flags();
+        let mut destinations = Vec::new();
+        if flags.logtostderr || flags.alsologtostderr {
+            destinations.push("stderr");
+        }
+        if !flags.logtostderr {
+            destinations.push("files");
+        }
+        let mut log_files = self
+            .file_writer
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(level, file)| (*level, file.lock().unwrap().path.clone()))
+            .collect::<Vec<_>>();
+        log_files.sort_by_key(|(level, _)| *level);
+        let mut target_files = self
+            .target_files
+            .read()
+            .unwrap()
+            .values()
+            .map(|file| file.lock().unwrap().path.clone())
+            .collect::<Vec<_>>();
+        target_files.sort();
+        f.debug_struct("Glog")
+            .field("initialized", &self.is_active())
+            .field("flags", &*flags)
+            .field("reduced_log_levels", &self.compatible_verbosity)
+            .field("with_year", &!self.compatible_date)
+            .field("verbosity_in_prefix", &self.verbosity_prefix)
+            .field("source_location", &self.source_location)
+            .field("header_working_directory", &self.header_working_directory)
+            .field("source_path", &self.source_path)
+            .field("thread_id_width", &self.thread_id_width)
+            .field("mmap_files", &self.mmap_files)
+            .field("append_log_files", &self.append_log_files)
+            .field("close_on_exec", &self.close_on_exec)
+            .field("index_interval", &self.index_interval)
+            .field("cascade_log_files", &self.cascade_log_files)
+            .field("crash_reports", &self.crash_reports)
+            .field("log_panics", &self.log_panics)
+            .field("resolve_backtraces", &self.backtraces.resolve)
+            .field("backtrace_depth", &self.backtraces.depth)
+            .field("short_backtraces", &self.backtraces.short)
+            .field("flush_on_exit", &self.flush_on_exit)
+            .field("describe_on_init", &self.describe_on_init)
+            .field("idle_flush", &self.idle_flush.as_ref().map(|idle_flush| idle_flush.idle()))
+            .field("sync_level", &self.sync_level)
+            .field("async_queue_size", &self.write_queue.as_ref().map(|queue| queue.capacity()))
+            .field("dropped_report_interval", &self.dropped_report_interval)
+            .field("wrapped_log_files", &self.file_wrapper.is_some())
+            .field("filter", &self.filter.is_some())
+            .field("before_write", &self.before_write.is_some())
+            .field("after_write", &self.after_write.is_some())
+            .field("included_messages", &self.included_messages)
+            .field("excluded_messages", &self.excluded_messages)
+            .field(
+                "first_occurrence",
+                &self.first_occurrence.as_ref().map(|(threshold, ..)| threshold),
+            )
+            .field("custom_file_header", &self.header_builder.is_some())
+            .field(
+                "journal",
+                &self.journal.as_ref().map(|journal| journal.lock().unwrap().0.clone()),
+            )
+            .field("recent_logs", &self.recent_logs.as_ref().map(|(count, _)| count))
+            .field("deterministic_output", &self.deterministic_output)
+            .field("max_message_length", &self.max_message_length)
+            .field("escape_control_characters", &self.escape_control_characters)
+            .field("sequence_numbers", &self.sequence_numbers)
+            .field("uptime", &self.uptime)
+            .field("application_fingerprint", &self.application_fingerprint)
+            .field("program_name", &self.program_name)
+            .field("hostname", &self.hostname)
+            .field("username", &self.username)
+            .field("start_time", &self.start_time)
+            .field("timestamp_clock", &self.timestamp_clock)
+            .field("destinations", &destinations)
+            .field("log_files", &log_files)
+            .field("level_dirs", &self.level_dirs)
+            .field("target_routes", &self.target_routes)
+            .field("level_remaps", &self.level_remaps)
+            .field("blocked_targets", &self.blocked_targets.targets())
+            .field("allowed_targets", &self.allowed_targets.targets())
+            .field("sampling", &self.sampling)
+            .field("target_files", &target_files)
+            .field("messages_logged", &self.messages_logged.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
 impl Default for Glog {
     fn default() -> Self {
         Glog::new()
@@ -535,6 +3616,508 @@ pub fn new() -> Glog {
     Glog::new()
 }
 
+/// Start a [`LogMessage`] at `level` which is emitted once it is dropped
+pub fn log(level: Level) -> LogMessage {
+    LogMessage::new(level)
+}
+
+/// Log `args` at `level` reporting the location of the caller
+///
+/// Mark wrapper functions with `#[track_caller]` so the messages show the call site of the
+/// wrapper instead of the wrapper itself.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// #[track_caller]
+/// fn log_request(id: u32) {
+///     glog::log_caller(Level::Info, format_args!("handling request {}", id));
+/// }
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// log_request(7);
+/// // I0401 12:34:56.987654   123 doc.rs:14] handling request 7
+/// ```
+#[track_caller]
+pub fn log_caller(level: Level, args: std::fmt::Arguments) {
+    LogMessage::new(level).caller_location().msg(args)
+}
+
+/// Log a message with an externally supplied source location
+///
+/// Useful for FFI shims and code generators which know the location of the original log
+/// statement. Unlike [`LogMessage::file_line`] the file name doesn't have to be `'static`. The
+/// message has an empty target, [`log_with_location!`] reports the module it is invoked in.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// let file = String::from("generated.cc");
+/// glog::log_at(Level::Warn, &file, 42, format_args!("from {}", "C++"));
+/// // W0401 12:34:56.987654   123 generated.cc:42] from C++
+/// ```
+pub fn log_at(level: Level, file: &str, line: u32, args: std::fmt::Arguments) {
+    __log_at(level, "", file, line, args)
+}
+
+/// Log a message of the module `target` with an externally supplied source location
+#[doc(hidden)]
+pub fn __log_at(level: Level, target: &str, file: &str, line: u32, args: std::fmt::Arguments) {
+    if level > log::STATIC_MAX_LEVEL || level > log::max_level() {
+        return;
+    }
+    log::logger().log(
+        &Record::builder()
+            .level(level)
+            .target(target)
+            .module_path(Some(target).filter(|target| !target.is_empty()))
+            .file(Some(file))
+            .line(Some(line))
+            .args(args)
+            .build(),
+    );
+}
+
+fn log_fatal(target: &str, file: &'static str, line: u32, args: std::fmt::Arguments) {
+    if is_initialized() {
+        LOGGING_FATAL.with(|fatal| fatal.set(true));
+        __log_at(Level::Error, target, file, line, args);
+        if let Some(glog) = LOGGER.get().filter(|glog| glog.crash_reports) {
+            glog.write_crash_report(target, file, line, args);
+        }
+        LOGGING_FATAL.with(|fatal| fatal.set(false));
+        log::logger().flush();
+    } else {
+        eprintln!("{}:{}] {}", file, line, args);
+    }
+}
+
+#[doc(hidden)]
+pub fn __fatal(target: &str, file: &'static str, line: u32, args: std::fmt::Arguments) -> ! {
+    log_fatal(target, file, line, args);
+    panics::fatal();
+    panic!("{}", args)
+}
+
+#[doc(hidden)]
+pub fn __qfatal(target: &str, file: &'static str, line: u32, args: std::fmt::Arguments) -> ! {
+    log_fatal(target, file, line, args);
+    std::process::exit(1)
+}
+
+/// Duplicate all messages into `writer`
+///
+/// This allows to show the log output in an application provided stream like an in-app console
+/// without implementing a complete sink. Messages are written in the same format as to the log
+/// files.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// glog::add_writer(Box::new(std::io::stdout()));
+///
+/// info!("Written to the log file and stdout");
+/// ```
+pub fn add_writer(writer: Box<dyn Write + Send>) {
+    WRITERS.lock().unwrap().push((writer, None));
+}
+
+/// Duplicate all messages into `writer` formatted by `formatter`
+///
+/// Like [`add_writer`] but each destination can choose its own format, e.g. [`format::json`] for
+/// a network stream or [`format::message`] for a GUI console.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// glog::add_writer_with_formatter(Box::new(std::io::stdout()), |record| {
+///     format!("[{}] {}", record.level, record.message)
+/// });
+///
+/// info!("Hello");
+/// // [INFO] Hello
+/// ```
+pub fn add_writer_with_formatter<F>(writer: Box<dyn Write + Send>, formatter: F)
+where
+    F: Fn(&RecordData) -> String + Send + Sync + 'static,
+{
+    WRITERS.lock().unwrap().push((writer, Some(Box::new(formatter))));
+}
+
+/// Flush all log destinations
+///
+/// Writes the buffered messages of all log files, writers and sinks, waiting for the
+/// [asynchronous writer](Flags::async_queue_size) first. The same as calling
+/// [`Log::flush`](log::Log::flush) on [`log::logger`] without having to import the trait.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags {
+///         logbufsecs: 30,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// info!("Buffered for up to 30s");
+/// glog::flush();
+/// ```
+pub fn flush() {
+    log::logger().flush();
+}
+
+/// Flush the log files of `level` and all more severe levels
+///
+/// This is the counterpart to `FlushLogFiles` in glog.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// warn!("Make sure this is on disk");
+/// glog::flush_log_files(Level::Warn);
+/// ```
+pub fn flush_log_files(level: Level) {
+    if let Some(glog) = LOGGER.get() {
+        glog.flush_files(level);
+    }
+}
+
+/// Full path of the current log file for `level`
+///
+/// Returns `None` if glog isn't initialized, logs to stderr only or the file has been closed.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// let path = glog::log_file_path(Level::Info).unwrap();
+/// println!("Logs are written to {}", path.display());
+/// ```
+pub fn log_file_path(level: Level) -> Option<PathBuf> {
+    let glog = LOGGER.get()?;
+    let files = glog.file_writer.read().unwrap();
+    let file = files.get(&glog.match_level(&level))?;
+    let path = PathBuf::from(&file.lock().unwrap().path);
+    Some(path)
+}
+
+/// All log files this process has written for `level`, oldest first
+///
+/// A new file is created whenever the log file of `level` is reopened, e.g. with
+/// [`enable_log_file`](LoggerHandle::enable_log_file). Files created within the same second get a
+/// sequence number appended to their name, which keeps them apart and in order. Other files next
+/// to the log files, like their [indexes](Glog::index_log_files), aren't part of the chain.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().index_log_files(1).init(Flags::default()).unwrap();
+///
+/// let logger = glog::logger().unwrap();
+/// warn!("Indexed");
+/// logger.disable_log_file(Level::Warn);
+/// logger.enable_log_file(Level::Warn).unwrap();
+/// warn!("Indexed as well");
+///
+/// let chain = glog::log_file_chain(Level::Warn);
+/// assert_eq!(chain.len(), 2);
+/// assert!(chain.iter().all(|path| path.extension().unwrap() != "idx"));
+/// assert_eq!(chain.last(), glog::log_file_path(Level::Warn).as_ref());
+/// ```
+pub fn log_file_chain(level: Level) -> Vec<PathBuf> {
+    let glog = match LOGGER.get() {
+        Some(glog) => glog,
+        None => return Vec::new(),
+    };
+    let level = glog.match_level(&level);
+    let mut prefix = glog.log_file_base(&glog.log_dir_of(level), &level.to_string().to_uppercase());
+    prefix.push(".");
+    let prefix = PathBuf::from(prefix);
+    let (dir, prefix) = match (prefix.parent(), prefix.file_name().and_then(|name| name.to_str())) {
+        (Some(dir), Some(prefix)) => (dir.to_owned(), prefix.to_owned()),
+        _ => return Vec::new(),
+    };
+    let pid = std::process::id().to_string();
+    // files of an earlier process with the same pid are older than the logger
+    let started = glog.start_time.format("%Y%m%d-%H%M%S").to_string();
+    let mut chain = std::fs::read_dir(&dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let mut parts = name.strip_prefix(&prefix)?.split('.');
+            let timestamp = parts.next()?.to_owned();
+            if parts.next()? != pid || timestamp < started {
+                return None;
+            }
+            let sequence = match parts.next() {
+                Some(sequence) => sequence.parse::<u32>().ok()?,
+                None => 0,
+            };
+            // e.g. the sidecar index of a log file
+            if parts.next().is_some() {
+                return None;
+            }
+            Some(((timestamp, sequence), dir.join(name)))
+        })
+        .collect::<Vec<_>>();
+    chain.sort();
+    chain.into_iter().map(|(_, path)| path).collect()
+}
+
+/// The information written to the log file headers
+///
+/// Returns `None` if glog hasn't been initialized.
+///
+/// # Example
+///
+/// ```
+/// use glog::Flags;
+///
+/// glog::new()
+///     .set_application_fingerprint("Example")
+///     .init(Flags::default())
+///     .unwrap();
+///
+/// let header = glog::header_info().unwrap();
+/// assert_eq!(header.application_fingerprint.as_deref(), Some("Example"));
+/// assert_eq!(header.pid, std::process::id());
+/// ```
+pub fn header_info() -> Option<HeaderInfo> {
+    LOGGER.get().map(Glog::header_info)
+}
+
+#[doc(hidden)]
+pub fn __with_counter<F: FnOnce()>(occurrences: usize, log: F) {
+    let previous = OCCURRENCES.with(|counter| counter.replace(occurrences));
+    log();
+    OCCURRENCES.with(|counter| counter.set(previous));
+}
+
+#[doc(hidden)]
+pub fn __counter() -> usize {
+    OCCURRENCES.with(Cell::get)
+}
+
+#[doc(hidden)]
+pub fn __with_verbosity<F: FnOnce()>(verbosity: i32, log: F) {
+    let previous = VERBOSITY.with(|current| current.replace(verbosity));
+    log();
+    VERBOSITY.with(|current| current.set(previous));
+}
+
+/// The [`v`](Flags::v) flag if Info messages of `target` pass the filters, otherwise `i32::MIN`
+///
+/// `None` if glog hasn't been initialized, so the registered logger has to decide.
+fn max_verbosity(target: &str) -> Option<i32> {
+    let glog = LOGGER.get()?;
+    if glog.enabled(&Metadata::builder().level(Level::Info).target(target).build()) {
+        Some(glog.flags().v)
+    } else {
+        Some(i32::MIN)
+    }
+}
+
+/// Statistics about the messages logged per level since glog has been initialized
+///
+/// Useful for services to monitor their own logging, e.g. to alert on a spike of errors.
+/// Empty if glog hasn't been initialized.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// error!("Something went wrong");
+///
+/// let stats = glog::stats();
+/// assert_eq!(stats.level(Level::Error).emitted, 1);
+/// assert!(stats.level(Level::Error).bytes_written > 0);
+/// ```
+pub fn stats() -> LogStats {
+    LOGGER.get().map(|glog| glog.stats.snapshot()).unwrap_or_default()
+}
+
+/// Path of the journal enabled with [`with_journal`](Glog::with_journal)
+pub fn journal_path() -> Option<PathBuf> {
+    let journal = LOGGER.get()?.journal.as_ref()?.lock().unwrap();
+    Some(PathBuf::from(&journal.0))
+}
+
+/// The last messages kept because of [`keep_recent_logs`](Glog::keep_recent_logs), oldest first
+pub fn recent_logs() -> Vec<String> {
+    match LOGGER.get().and_then(|glog| glog.recent_logs.as_ref()) {
+        Some((_, recent_logs)) => recent_logs.lock().unwrap().iter().cloned().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Check whether glog has been initialized and not been shut down yet
+///
+/// This is the counterpart to `IsGoogleLoggingInitialized` in glog.
+/// Libraries can use it to decide whether they have to set up logging themselves or whether the
+/// application already took care of it.
+///
+/// # Example
+///
+/// ```
+/// use glog::Flags;
+///
+/// if !glog::is_initialized() {
+///     glog::new().init(Flags::default()).unwrap();
+/// }
+///
+/// assert!(glog::is_initialized());
+/// ```
+pub fn is_initialized() -> bool {
+    INITIALIZED.load(Ordering::SeqCst)
+}
+
+/// Give a forked child process its own log files
+///
+/// A child created with `fork` shares the log files and the journal with its parent and lacks
+/// the background threads of the logger, like the writer thread of the
+/// [asynchronous writer](Flags::async_queue_size). Called in the child right after the fork this
+/// opens new log files named with the pid of the child, restarts the background threads and
+/// discards the messages buffered or queued by the parent, which writes them itself. Threads
+/// started by the application, e.g. to flush periodically, have to be restarted separately.
+///
+/// As for anything else in a forked child of a multithreaded process, no other thread of the
+/// parent may be logging at the time of the fork.
+///
+/// # Example
+///
+/// ```no_run
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// // SAFETY: the process is single threaded
+/// if let Ok(nix::unistd::ForkResult::Child) = unsafe { nix::unistd::fork() } {
+///     glog::atfork_child();
+///     info!("Written to the log files of the child");
+/// }
+/// ```
+#[cfg(target_family = "unix")]
+pub fn atfork_child() {
+    if let Some(glog) = LOGGER.get().filter(|_| is_initialized()) {
+        glog.atfork_child();
+    }
+}
+
+/// Flush and close all log files
+///
+/// This is the counterpart to `ShutdownGoogleLogging` in glog.
+/// The [asynchronous writer](Flags::async_queue_size) writes the queued messages and stops, as
+/// does the thread started by [`flush_when_idle`](Glog::flush_when_idle). Messages logged after
+/// the shutdown are written to stderr only.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+/// use std::{fs, thread, time::Duration};
+///
+/// glog::new()
+///     .flush_when_idle(Duration::from_millis(10))
+///     .init(Flags {
+///         async_queue_size: 16,
+///         logbufsecs: 30,
+///         ..Default::default()
+///     }).unwrap();
+/// let path = glog::log_file_path(Level::Info).unwrap();
+///
+/// for i in 0..100 {
+///     info!("Written to the log file {}", i);
+/// }
+///
+/// glog::shutdown();
+/// assert!(!glog::is_initialized());
+/// let written = fs::read_to_string(&path).unwrap();
+/// assert!(written.contains("Written to the log file 99"));
+///
+/// info!("Written to stderr only");
+/// thread::sleep(Duration::from_millis(50));
+/// assert_eq!(fs::read_to_string(&path).unwrap(), written);
+/// ```
+pub fn shutdown() {
+    if let Some(glog) = LOGGER.get() {
+        INITIALIZED.store(false, Ordering::SeqCst);
+        if let Some(idle_flush) = &glog.idle_flush {
+            idle_flush.stop();
+        }
+        if let Some(write_queue) = &glog.write_queue {
+            glog.report_dropped(true);
+            write_queue.close();
+        }
+        glog.flush();
+        let mut files = glog.file_writer.write().unwrap();
+        for level in files.keys() {
+            raw_files::unregister(*level);
+        }
+        files.clear();
+        glog.target_files.write().unwrap().clear();
+    }
+}
+
+/// Keep `file` open in processes started with `exec`, files are opened with `O_CLOEXEC` by std
+#[cfg(target_family = "unix")]
+fn inherit_on_exec(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    use nix::libc;
+
+    // SAFETY: F_SETFD only changes the flags of the descriptor owned by file
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETFD, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     // todo(#6): Fill with tests
@@ -0,0 +1,439 @@
+/// Log a message with an explicitly supplied source location
+///
+/// Works like the logging macros of the [`standard logging`] frontend but reports `file:line`
+/// instead of the location of the macro invocation. The target is still the module the macro is
+/// invoked in, like for the other macros of glog.
+///
+/// [`standard logging`]: https://crates.io/crates/log
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new()
+///     .with_filter(|record| record.target == module_path!())
+///     .init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// glog::log_with_location!("generated.rs", 42, Level::Info, "from {}", "elsewhere");
+/// // I0401 12:34:56.987654   123 generated.rs:42] from elsewhere
+/// ```
+#[macro_export]
+macro_rules! log_with_location {
+    ($file:expr, $line:expr, $level:expr, $($arg:tt)+) => {
+        $crate::__log_at($level, module_path!(), $file, $line, format_args!($($arg)+))
+    };
+}
+
+/// Log a message at the most severe level and terminate by panicking
+///
+/// glog aborts the process after logging a `FATAL` message. As there is no fatal level in
+/// the [`standard logging`] frontend the message is logged as `Error`, all destinations get
+/// flushed and the current thread panics with the same message.
+///
+/// If glog isn't initialized the message is printed to stderr before panicking.
+///
+/// [`standard logging`]: https://crates.io/crates/log
+///
+/// # Example
+///
+/// ```should_panic
+/// use glog::{fatal, Flags};
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// fatal!("Can't continue without {}", "config");
+/// ```
+#[macro_export]
+macro_rules! fatal {
+    ($($arg:tt)+) => {
+        $crate::__fatal(module_path!(), file!(), line!(), format_args!($($arg)+))
+    };
+}
+
+/// Log a message at the most severe level and exit quietly
+///
+/// This is the counterpart to `QFATAL` in glog. Works like [`fatal!`] but exits the process
+/// with status 1 instead of panicking, so neither a panic message nor a backtrace is printed.
+/// Useful for expected failures like an invalid configuration at startup.
+///
+/// # Example
+///
+/// ```no_run
+/// use glog::{qfatal, Flags};
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// qfatal!("Invalid configuration: {}", "port out of range");
+/// ```
+#[macro_export]
+macro_rules! qfatal {
+    ($($arg:tt)+) => {
+        $crate::__qfatal(module_path!(), file!(), line!(), format_args!($($arg)+))
+    };
+}
+
+/// Log an error together with the chain of its sources
+///
+/// Each cause returned by [`Error::source`](std::error::Error::source) is written on its own
+/// `caused by:` line below the error instead of being lost or flattened into a single line.
+/// An optional message in the format of [`format!`] is written in front of the error. Pass boxed
+/// errors dereferenced, e.g. `*error`.
+///
+/// # Example
+///
+/// ```
+/// use std::{fmt, io};
+/// use log::*;
+/// use glog::{log_error, Flags};
+///
+/// #[derive(Debug)]
+/// struct ConfigError(io::Error);
+///
+/// impl fmt::Display for ConfigError {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         write!(f, "can't load the configuration")
+///     }
+/// }
+///
+/// impl std::error::Error for ConfigError {
+///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// let error = ConfigError(io::Error::new(io::ErrorKind::NotFound, "server.toml not found"));
+/// log_error!(Level::Error, error, "Startup of {} failed", "server");
+/// let boxed: Box<dyn std::error::Error> = Box::new(error);
+/// log_error!(Level::Warn, *boxed);
+/// // E0401 12:34:56.987654   123 doc.rs:27] Startup of server failed: can't load the configuration
+/// //   caused by: server.toml not found
+/// // W0401 12:34:56.987700   123 doc.rs:29] can't load the configuration
+/// //   caused by: server.toml not found
+/// ```
+#[macro_export]
+macro_rules! log_error {
+    ($level:expr, $error:expr) => {
+        $crate::__log_at(
+            $level,
+            module_path!(),
+            file!(),
+            line!(),
+            format_args!("{}", $crate::ErrorChain::new(&$error)),
+        )
+    };
+    ($level:expr, $error:expr, $($arg:tt)+) => {
+        $crate::__log_at(
+            $level,
+            module_path!(),
+            file!(),
+            line!(),
+            format_args!("{}: {}", format_args!($($arg)+), $crate::ErrorChain::new(&$error)),
+        )
+    };
+}
+
+/// Terminate like [`fatal!`] if a system call returns `-1`, otherwise evaluate to its result
+///
+/// This is the counterpart to `CHECK_ERR` in glog. The fatal message contains the failed
+/// expression and the text of `errno`, which is read right after the expression is evaluated.
+///
+/// # Example
+///
+/// ```should_panic
+/// # #[cfg(target_family = "unix")]
+/// # {
+/// use nix::libc;
+/// use glog::{check_err, Flags};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// let fd = check_err!(unsafe { libc::dup(2) });
+/// check_err!(unsafe { libc::close(fd) });
+/// check_err!(unsafe { libc::close(fd) });
+/// // E0401 12:34:56.987654   123 doc.rs:12] Check failed: unsafe { libc::close(fd) } != -1: Bad file descriptor (os error 9)
+/// # }
+/// # #[cfg(not(target_family = "unix"))]
+/// # panic!();
+/// ```
+#[macro_export]
+macro_rules! check_err {
+    ($expr:expr) => {{
+        let result = $expr;
+        if result == -1 {
+            let error = ::std::io::Error::last_os_error();
+            $crate::__fatal(
+                module_path!(),
+                file!(),
+                line!(),
+                format_args!("Check failed: {} != -1: {}", stringify!($expr), error),
+            );
+        }
+        result
+    }};
+}
+
+/// Log an error if `result` indicates a failure
+///
+/// This is the counterpart to `LOG_SYSRESULT` in glog. `result` is either an `HRESULT` or a
+/// [`SysResult`](crate::SysResult), e.g. [`SysResult::last_error`](crate::SysResult::last_error).
+/// Failed results are logged as `Error` with their system message and hexadecimal code appended.
+/// An optional message in the format of [`format!`] is written in front of it. Evaluates to
+/// whether `result` failed.
+///
+/// # Example
+///
+/// ```
+/// use glog::{log_sysresult, Flags};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// // E_ACCESSDENIED returned by a COM call
+/// let result = 0x80070005_u32 as i32;
+/// assert!(log_sysresult!(result, "Opening the {} failed", "registry key"));
+/// assert!(!log_sysresult!(0));
+/// assert!(log_sysresult!(glog::SysResult::error_code(2), "Opening the {} failed", "file"));
+/// // E0401 12:34:56.987654   123 doc.rs:10] Opening the registry key failed: Access is denied. (os error -2147024891) [0x80070005]
+/// ```
+#[macro_export]
+macro_rules! log_sysresult {
+    ($result:expr) => {{
+        let result = $crate::SysResult::from($result);
+        if result.failed() {
+            $crate::__log_at(
+                $crate::__log::Level::Error,
+                module_path!(),
+                file!(),
+                line!(),
+                format_args!("{}", result),
+            );
+        }
+        result.failed()
+    }};
+    ($result:expr, $($arg:tt)+) => {{
+        let result = $crate::SysResult::from($result);
+        if result.failed() {
+            $crate::__log_at(
+                $crate::__log::Level::Error,
+                module_path!(),
+                file!(),
+                line!(),
+                format_args!("{}: {}", format_args!($($arg)+), result),
+            );
+        }
+        result.failed()
+    }};
+}
+
+/// Log every `n`th occurrence of this statement, starting with the first one
+///
+/// This is the counterpart to `LOG_EVERY_N` in glog. Use [`counter!`] in the message to get
+/// the number of times this statement has been reached. With an `n` of 0 nothing is logged.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{counter, log_every_n, Flags};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// for _ in 0..250 {
+///     log_every_n!(Level::Info, 100, "processed {} batches", counter!());
+///     log_every_n!(Level::Info, 0, "Not logged");
+/// }
+/// // I0401 12:34:56.987654   123 doc.rs:10] processed 1 batches
+/// // I0401 12:34:56.987700   123 doc.rs:10] processed 101 batches
+/// // I0401 12:34:56.987800   123 doc.rs:10] processed 201 batches
+/// ```
+#[macro_export]
+macro_rules! log_every_n {
+    ($level:expr, $n:expr, $($arg:tt)+) => {{
+        static OCCURRENCES: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
+        let occurrences = OCCURRENCES.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) + 1;
+        let n = $n as usize;
+        if n > 0 && (occurrences - 1) % n == 0 {
+            $crate::__with_counter(occurrences, || $crate::__log::log!($level, $($arg)+));
+        }
+    }};
+}
+
+/// Log only the first `n` occurrences of this statement
+///
+/// This is the counterpart to `LOG_FIRST_N` in glog. Use [`counter!`] in the message to get
+/// the number of times this statement has been reached.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{counter, log_first_n, Flags};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// for _ in 0..10 {
+///     log_first_n!(Level::Warn, 2, "retry {}", counter!());
+/// }
+/// // W0401 12:34:56.987654   123 doc.rs:10] retry 1
+/// // W0401 12:34:56.987700   123 doc.rs:10] retry 2
+/// ```
+#[macro_export]
+macro_rules! log_first_n {
+    ($level:expr, $n:expr, $($arg:tt)+) => {{
+        static OCCURRENCES: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
+        let occurrences = OCCURRENCES.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) + 1;
+        if occurrences <= ($n as usize) {
+            $crate::__with_counter(occurrences, || $crate::__log::log!($level, $($arg)+));
+        }
+    }};
+}
+
+/// Number of times the surrounding [`log_every_n!`] or [`log_first_n!`] statement has been reached
+///
+/// This is the counterpart to `COUNTER` in glog. Outside of these macros it evaluates to 0.
+#[macro_export]
+macro_rules! counter {
+    () => {
+        $crate::__counter()
+    };
+}
+
+/// Check whether messages with `verbosity` are logged
+///
+/// This is the counterpart to `VLOG_IS_ON` in glog.
+///
+/// Whether the module passes the filters is decided once per invocation and cached until the
+/// flags change. If glog isn't [initialized](crate::Glog::init) the logger installed with the
+/// [`standard logging`](https://crates.io/crates/log) frontend decides instead, e.g. one created
+/// with [`build_boxed`](crate::Glog::build_boxed), which drops the messages above its
+/// [`v`](crate::Flags::v) flag itself.
+///
+/// With the `strip-vlog` feature this is always `false` and `verbosity` isn't evaluated.
+#[cfg(not(feature = "strip-vlog"))]
+#[macro_export]
+macro_rules! vlog_is_on {
+    ($verbosity:expr) => {
+        // verbose messages are Info messages, which may be removed at compile time
+        $crate::__log::Level::Info <= $crate::__log::STATIC_MAX_LEVEL && {
+            // the filters are only evaluated again once the flags change
+            static CALLSITE: $crate::__Callsite = $crate::__Callsite::new();
+            CALLSITE.vlog_is_on($verbosity, module_path!())
+        }
+    };
+}
+
+/// Check whether messages with `verbosity` are logged
+///
+/// This is the counterpart to `VLOG_IS_ON` in glog.
+///
+/// With the `strip-vlog` feature this is always `false` and `verbosity` isn't evaluated.
+#[cfg(feature = "strip-vlog")]
+#[macro_export]
+macro_rules! vlog_is_on {
+    ($verbosity:expr) => {
+        false
+    };
+}
+
+/// Log a verbose message if `verbosity` is at most the [`v`](crate::Flags::v) flag
+///
+/// This is the counterpart to `VLOG` in glog. Verbose messages are logged as `Info`.
+///
+/// # Example
+///
+/// ```
+/// use glog::{vlog, Flags};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         v: 1,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// vlog!(1, "Logged");
+/// vlog!(2, "Not logged");
+/// // I0401 12:34:56.987654   123 doc.rs:9] Logged
+/// ```
+///
+/// With the `strip-vlog` feature the macro expands to nothing. Neither the verbosity nor the
+/// arguments are evaluated and the binary doesn't contain the format strings.
+#[cfg(not(feature = "strip-vlog"))]
+#[macro_export]
+macro_rules! vlog {
+    ($verbosity:expr, $($arg:tt)+) => {{
+        let verbosity: i32 = $verbosity;
+        if $crate::vlog_is_on!(verbosity) {
+            $crate::__with_verbosity(verbosity, || $crate::__log::log!($crate::__log::Level::Info, $($arg)+));
+        }
+    }};
+}
+
+/// Log a verbose message if `verbosity` is at most the [`v`](crate::Flags::v) flag
+///
+/// This is the counterpart to `VLOG` in glog. Verbose messages are logged as `Info`.
+///
+/// With the `strip-vlog` feature the macro expands to nothing. Neither the verbosity nor the
+/// arguments are evaluated and the binary doesn't contain the format strings.
+#[cfg(feature = "strip-vlog")]
+#[macro_export]
+macro_rules! vlog {
+    ($verbosity:expr, $($arg:tt)+) => {{}};
+}
+
+/// Log how long the current scope took once it is left
+///
+/// Returns a [`DurationGuard`](crate::DurationGuard) which logs `name` and the elapsed time at
+/// `level` when dropped. With a `threshold` only scopes taking at least as long are logged.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use log::*;
+/// use glog::{log_duration, Flags};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// {
+///     let _timer = log_duration!(Level::Info, "load_index");
+///     // load the index
+/// }
+/// {
+///     let _timer = log_duration!(Level::Warn, "query", Duration::from_millis(100));
+///     // only logged if the query is slow
+/// }
+/// // I0401 12:34:56.987654   123 doc.rs:11] load_index took 1.234µs
+/// ```
+#[macro_export]
+macro_rules! log_duration {
+    ($level:expr, $name:expr) => {
+        $crate::__duration_guard($level, $name, None, module_path!(), file!(), line!())
+    };
+    ($level:expr, $name:expr, $threshold:expr) => {
+        $crate::__duration_guard($level, $name, Some($threshold), module_path!(), file!(), line!())
+    };
+}
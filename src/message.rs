@@ -0,0 +1,116 @@
+use std::{
+    fmt::{self, Write},
+    panic::Location,
+};
+
+use log::{Level, Metadata, Record};
+
+/// A log message which is emitted once it is dropped
+///
+/// This is an alternative to the logging macros for code which builds its messages piece by
+/// piece, like code generated from C++ using the `LOG(INFO) << x` idiom.
+/// Create one with [`log`](crate::log()).
+///
+/// # Example
+///
+/// ```
+/// use std::fmt::Write;
+/// use log::*;
+/// use glog::Flags;
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// glog::log(Level::Info).file_line(file!(), line!()).msg(format_args!("{} + {} = {}", 1, 2, 1 + 2));
+///
+/// let mut message = glog::log(Level::Warn).file_line(file!(), line!());
+/// write!(message, "streamed ").unwrap();
+/// write!(message, "in {} parts", 2).unwrap();
+/// drop(message);
+/// // I0401 12:34:56.987654   123 doc.rs:11] 1 + 2 = 3
+/// // W0401 12:34:56.987700   123 doc.rs:13] streamed in 2 parts
+/// ```
+pub struct LogMessage {
+    level: Level,
+    target: &'static str,
+    file: Option<&'static str>,
+    line: Option<u32>,
+    enabled: bool,
+    buffer: String,
+}
+
+impl LogMessage {
+    pub(crate) fn new(level: Level) -> Self {
+        LogMessage {
+            level,
+            target: "",
+            file: None,
+            line: None,
+            enabled: LogMessage::is_enabled(level, ""),
+            buffer: String::new(),
+        }
+    }
+
+    fn is_enabled(level: Level, target: &str) -> bool {
+        level <= log::STATIC_MAX_LEVEL
+            && level <= log::max_level()
+            && log::logger().enabled(&Metadata::builder().level(level).target(target).build())
+    }
+
+    /// Set the source location reported for this message
+    pub fn file_line(mut self, file: &'static str, line: u32) -> Self {
+        self.file = Some(file);
+        self.line = Some(line);
+        self
+    }
+
+    /// Report the location of the caller as the source location of this message
+    ///
+    /// Together with `#[track_caller]` on wrapper functions this reports the real call site
+    /// instead of the location inside the wrapper.
+    #[track_caller]
+    pub fn caller_location(self) -> Self {
+        let location = Location::caller();
+        self.file_line(location.file(), location.line())
+    }
+
+    /// Set the target reported for this message
+    pub fn target(mut self, target: &'static str) -> Self {
+        self.target = target;
+        self.enabled = LogMessage::is_enabled(self.level, target);
+        self
+    }
+
+    /// Append `args` to the message and emit it
+    pub fn msg(mut self, args: fmt::Arguments) {
+        let _ = self.write_fmt(args);
+    }
+}
+
+impl Write for LogMessage {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.enabled {
+            self.buffer.push_str(s);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LogMessage {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        log::logger().log(
+            &Record::builder()
+                .level(self.level)
+                .target(self.target)
+                .file_static(self.file)
+                .line(self.line)
+                .args(format_args!("{}", self.buffer))
+                .build(),
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    fs::File,
+    io::{self, IoSlice, Write},
+    os::unix::io::AsRawFd,
+    ptr,
+    slice,
+};
+
+use nix::libc;
+
+/// The mapping grows by this many bytes whenever it is full
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Appends to a file through a shared memory mapping instead of write syscalls
+///
+/// The file is extended in chunks and truncated to the written length once the writer is dropped.
+pub(crate) struct MmapWriter {
+    file: File,
+    map: *mut u8,
+    capacity: usize,
+    len: usize,
+}
+
+// SAFETY: the mapping is owned exclusively by the writer and only accessed through &mut self
+unsafe impl Send for MmapWriter {}
+
+impl MmapWriter {
+    /// Map `file` and continue writing after its current content
+    pub(crate) fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        let mut writer = MmapWriter {
+            file,
+            map: ptr::null_mut(),
+            capacity: 0,
+            len,
+        };
+        writer.grow(len + CHUNK_SIZE)?;
+        Ok(writer)
+    }
+
+    pub(crate) fn file(&self) -> &File {
+        &self.file
+    }
+
+    fn grow(&mut self, min_capacity: usize) -> io::Result<()> {
+        let capacity = (min_capacity / CHUNK_SIZE + 1) * CHUNK_SIZE;
+        self.unmap();
+        self.file.set_len(capacity as u64)?;
+        // SAFETY: the file has just been extended to `capacity` so the mapping is backed by the file
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                capacity,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                self.file.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            let _ = self.file.set_len(self.len as u64);
+            return Err(io::Error::last_os_error());
+        }
+        self.map = map as *mut u8;
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    fn unmap(&mut self) {
+        if !self.map.is_null() {
+            // SAFETY: map and capacity describe the mapping created in grow
+            unsafe {
+                libc::munmap(self.map as *mut libc::c_void, self.capacity);
+            }
+            self.map = ptr::null_mut();
+            self.capacity = 0;
+        }
+    }
+}
+
+impl Write for MmapWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len + buf.len() > self.capacity {
+            self.grow(self.len + buf.len())?;
+        }
+        // SAFETY: the mapping has room for buf after the written bytes
+        unsafe {
+            slice::from_raw_parts_mut(self.map.add(self.len), buf.len()).copy_from_slice(buf);
+        }
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+        if self.len + len > self.capacity {
+            self.grow(self.len + len)?;
+        }
+        for buf in bufs {
+            // SAFETY: the mapping has room for all bufs after the written bytes
+            unsafe {
+                slice::from_raw_parts_mut(self.map.add(self.len), buf.len()).copy_from_slice(buf);
+            }
+            self.len += buf.len();
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // nothing has been mapped yet
+        if self.map.is_null() {
+            return Ok(());
+        }
+        // SAFETY: map and capacity describe the mapping created in grow
+        if unsafe { libc::msync(self.map as *mut libc::c_void, self.capacity, libc::MS_ASYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MmapWriter {
+    fn drop(&mut self) {
+        self.unmap();
+        let _ = self.file.set_len(self.len as u64);
+    }
+}
@@ -0,0 +1,62 @@
+use std::{cell::Cell, panic, sync::Once, thread};
+
+use log::{Level, Record};
+
+use crate::trace;
+
+/// Target of the logged panics
+pub(crate) const TARGET: &str = "panic";
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    // the panic of fatal! has already been logged
+    static FATAL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Skip logging the next panic of the calling thread
+pub(crate) fn fatal() {
+    if INSTALL.is_completed() {
+        FATAL.with(|fatal| fatal.set(true));
+    }
+}
+
+/// Log the panics of all threads at ERROR before passing them on to the previous panic hook
+pub(crate) fn install() {
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if FATAL.with(|fatal| fatal.replace(false)) {
+                previous(info);
+                return;
+            }
+            let payload = info
+                .payload()
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("Box<dyn Any>");
+            let backtrace = match crate::LOGGER.get() {
+                Some(glog) => trace::capture_panic(glog.backtraces),
+                None => String::new(),
+            };
+            let thread = thread::current();
+            log::logger().log(
+                &Record::builder()
+                    .level(Level::Error)
+                    .target(TARGET)
+                    .file(info.location().map(|location| location.file()))
+                    .line(info.location().map(|location| location.line()))
+                    .args(format_args!(
+                        "thread '{}' panicked: {}\n{}",
+                        thread.name().unwrap_or("<unnamed>"),
+                        payload,
+                        backtrace
+                    ))
+                    .build(),
+            );
+            log::logger().flush();
+            previous(info);
+        }));
+    });
+}
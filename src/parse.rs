@@ -0,0 +1,471 @@
+//! Parser for log files written in the glog format
+//!
+//! Reads the file headers, restart markers and messages of log files back into structured
+//! entries, so tooling can analyze the logs it produced. Lines which don't start a new message,
+//! like the continuation of a multi-line message or a backtrace, belong to the message before
+//! them.
+//!
+//! # Example
+//!
+//! ```
+//! use log::Level;
+//! use glog::parse::{self, Entry};
+//!
+//! let log = "Log file created at:
+//! 2021/04/01 12:34:56
+//! Running on machine: localhost
+//! Command line: server --port 8080
+//! Running duration (h:mm:ss): 0:00:00
+//! Log line format: [IWE]mmdd hh:mm:ss.uuuuuu threadid #sequence file:line] msg
+//! I0401 12:34:56.987654  1234 #1 main.rs:12] Listening
+//! W0401 12:34:57.000001  1234 #2 main.rs:20] Slow request
+//! took 3s
+//!    0: server::handle
+//!              at src/main.rs:20:5
+//! ";
+//!
+//! let entries = parse::parse(log);
+//! assert!(matches!(&entries[0], Entry::Header(header) if header.hostname.as_deref() == Some("localhost")));
+//!
+//! let records = entries.iter().filter_map(Entry::record).collect::<Vec<_>>();
+//! assert_eq!(records.len(), 2);
+//! assert_eq!(records[0].level(), Some(Level::Info));
+//! assert_eq!(records[0].file.as_deref(), Some("main.rs"));
+//! assert_eq!(records[0].line, Some(12));
+//! assert_eq!(records[1].sequence, Some(2));
+//! assert_eq!(records[1].message, "Slow request\ntook 3s");
+//! assert_eq!(records[1].timestamp.to_string(), "2021-04-01 12:34:57.000001");
+//! assert!(records[1].backtrace.as_deref().unwrap().starts_with("   0: server::handle"));
+//! ```
+
+use std::{fs, io, path::Path};
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use log::Level;
+
+/// The header at the start of a log file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileHeader {
+    /// When the log file was created
+    pub created_at: Option<NaiveDateTime>,
+    /// The `Running on machine` line
+    pub hostname: Option<String>,
+    /// The `Command line` line
+    pub command_line: Option<String>,
+    /// The `Working directory` line
+    pub working_directory: Option<String>,
+    /// The `Application fingerprint` line
+    pub application_fingerprint: Option<String>,
+    /// The `Log line format` line
+    pub line_format: Option<String>,
+}
+
+/// A message read from a log file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsedRecord {
+    /// The severity letter at the start of the line, `V` for verbose messages
+    pub severity: char,
+    /// The verbosity of a `V` message
+    ///
+    /// The timestamp follows the verbosity without a separator. With a monotonic timestamp only,
+    /// the boundary between them can't be told, so the verbosity is taken to be a single digit.
+    pub verbosity: Option<u32>,
+    /// When the message was logged
+    ///
+    /// Timestamps without year get the year of the preceding file header, or the current year if
+    /// there is none. Lines with only a monotonic timestamp get the start time of the logger
+    /// according to the header plus the monotonic time.
+    pub timestamp: NaiveDateTime,
+    /// The thread and/or process id, as described by the `Log line format` of the header
+    pub ids: Vec<u64>,
+    /// The sequence number written with [`sequence_numbers`](crate::Glog::sequence_numbers)
+    pub sequence: Option<u64>,
+    /// The time since glog has been created written with [`uptime`](crate::Glog::uptime)
+    pub uptime: Option<std::time::Duration>,
+    /// The monotonic nanoseconds written with [`timestamp_clock`](crate::Glog::timestamp_clock)
+    pub monotonic_ns: Option<u64>,
+    /// The source file, if the source location is written
+    pub file: Option<String>,
+    /// The source line, if the source location is written
+    pub line: Option<u32>,
+    /// The message text including the lines continuing it
+    pub message: String,
+    /// A backtrace logged after the message
+    pub backtrace: Option<String>,
+}
+
+impl ParsedRecord {
+    /// The level matching the severity, `None` for verbose messages
+    pub fn level(&self) -> Option<Level> {
+        match self.severity {
+            'T' => Some(Level::Trace),
+            'D' => Some(Level::Debug),
+            'I' => Some(Level::Info),
+            'W' => Some(Level::Warn),
+            'E' | 'F' => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+/// An entry of a log file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Entry {
+    /// The header written when the file was created
+    Header(FileHeader),
+    /// The marker written when another run starts appending to the file
+    Restart {
+        /// When the run started
+        restarted_at: Option<NaiveDateTime>,
+        /// Process id of the run
+        pid: Option<u32>,
+    },
+    /// A message
+    Record(ParsedRecord),
+}
+
+impl Entry {
+    /// The message if this entry is one
+    pub fn record(&self) -> Option<&ParsedRecord> {
+        match self {
+            Entry::Record(record) => Some(record),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the content of a log file
+pub fn parse(text: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut year = Local::now().year();
+    let mut month = None;
+    // the number of digits of the date according to the header
+    let mut date_digits = None;
+    // when the logger of the current run was created
+    let mut start = None;
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        if line == "Log file created at:" {
+            let mut header = FileHeader {
+                created_at: lines.next().and_then(parse_header_time),
+                ..Default::default()
+            };
+            let mut running_duration = Duration::zero();
+            while let Some(line) = lines.next_if(|line| parse_record_line(line, year, None, None).is_none()) {
+                if let Some(hostname) = line.strip_prefix("Running on machine: ") {
+                    header.hostname = Some(hostname.to_owned());
+                } else if let Some(command_line) = line.strip_prefix("Command line: ") {
+                    header.command_line = Some(command_line.to_owned());
+                } else if let Some(working_directory) = line.strip_prefix("Working directory: ") {
+                    header.working_directory = Some(working_directory.to_owned());
+                } else if let Some(fingerprint) = line.strip_prefix("Application fingerprint: ") {
+                    header.application_fingerprint = Some(fingerprint.to_owned());
+                } else if let Some(duration) = line.strip_prefix("Running duration (h:mm:ss): ") {
+                    running_duration = parse_duration(duration).unwrap_or(running_duration);
+                } else if let Some(line_format) = line.strip_prefix("Log line format: ") {
+                    header.line_format = Some(line_format.to_owned());
+                    break;
+                }
+            }
+            date_digits = match &header.line_format {
+                Some(line_format) if line_format.contains("yyyymmdd") => Some(8),
+                Some(line_format) if line_format.contains("mmdd") => Some(4),
+                _ => None,
+            };
+            if let Some(created_at) = header.created_at {
+                year = created_at.year();
+                month = Some(created_at.month());
+                start = Some(created_at - running_duration);
+            }
+            entries.push(Entry::Header(header));
+        } else if let Some(restart) = line.strip_prefix("Log file restarted at: ") {
+            let (restarted_at, pid) = match restart.split_once(" by pid ") {
+                Some((restarted_at, pid)) => (parse_header_time(restarted_at), pid.parse().ok()),
+                None => (parse_header_time(restart), None),
+            };
+            if let Some(restarted_at) = restarted_at {
+                year = restarted_at.year();
+                month = Some(restarted_at.month());
+                start = Some(restarted_at);
+            }
+            entries.push(Entry::Restart {
+                restarted_at,
+                pid,
+            });
+        } else if let Some((mut record, absolute)) = parse_record_line(line, year, start, date_digits) {
+            // timestamps without year continue in the next year once the month wraps around
+            if !absolute && month.is_some_and(|month| record.timestamp.month() < month) {
+                year += 1;
+                record.timestamp = record.timestamp.with_year(year).unwrap_or(record.timestamp);
+            }
+            month = Some(record.timestamp.month());
+            entries.push(Entry::Record(record));
+        } else if let Some(Entry::Record(record)) = entries.last_mut() {
+            if record.backtrace.is_none() && is_backtrace_frame(line) {
+                record.backtrace = Some(line.to_owned());
+            } else if let Some(backtrace) = &mut record.backtrace {
+                backtrace.push('\n');
+                backtrace.push_str(line);
+            } else {
+                record.message.push('\n');
+                record.message.push_str(line);
+            }
+        }
+    }
+    entries
+}
+
+/// Read and parse the log file at `path`
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{parse, Flags};
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// info!("Parsed");
+/// glog::flush();
+///
+/// let entries = parse::parse_file(glog::log_file_path(Level::Info).unwrap()).unwrap();
+/// let record = entries.iter().filter_map(parse::Entry::record).last().unwrap();
+/// assert_eq!(record.message, "Parsed");
+/// ```
+///
+/// Verbose messages written with [`verbosity_in_prefix`](crate::Glog::verbosity_in_prefix):
+///
+/// ```
+/// use log::*;
+/// use glog::{parse, vlog, Flags};
+/// # // verbose messages are removed by the strip-vlog feature
+/// # if cfg!(feature = "strip-vlog") {
+/// #     return;
+/// # }
+///
+/// glog::new()
+///     .verbosity_in_prefix(true)
+///     .init(Flags {
+///         v: 12,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// info!("Not verbose");
+/// vlog!(2, "Verbose");
+/// vlog!(12, "Very verbose");
+/// glog::flush();
+///
+/// let entries = parse::parse_file(glog::log_file_path(Level::Info).unwrap()).unwrap();
+/// let records = entries.iter().filter_map(parse::Entry::record).collect::<Vec<_>>();
+/// assert_eq!(records.len(), 3);
+/// assert_eq!((records[1].severity, records[1].verbosity), ('V', Some(2)));
+/// assert_eq!(records[1].message, "Verbose");
+/// assert_eq!(records[2].verbosity, Some(12));
+/// assert_eq!(records[2].timestamp.date(), records[0].timestamp.date());
+/// ```
+pub fn parse_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<Entry>> {
+    Ok(parse(&String::from_utf8_lossy(&fs::read(path)?)))
+}
+
+/// Merge the messages of several log files of one run into a single chronological list
+///
+/// Meant for the files of the different levels, which contain the messages of more severe levels
+/// as well. Messages found in several files are only returned once, identical messages logged
+/// repeatedly within the same microsecond are kept as often as they occur in a single file.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{parse, Flags};
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// info!("first");
+/// error!("second");
+/// warn!("third");
+/// glog::flush();
+///
+/// let paths = [Level::Info, Level::Warn, Level::Error]
+///     .iter()
+///     .filter_map(|level| glog::log_file_path(*level))
+///     .collect::<Vec<_>>();
+/// let messages = parse::merge(&paths)
+///     .unwrap()
+///     .into_iter()
+///     .map(|record| record.message)
+///     .collect::<Vec<_>>();
+/// assert_eq!(messages, ["first", "second", "third"]);
+/// ```
+pub fn merge<P: AsRef<Path>>(paths: &[P]) -> io::Result<Vec<ParsedRecord>> {
+    let mut records = Vec::new();
+    for (file, path) in paths.iter().enumerate() {
+        records.extend(parse_file(path)?.into_iter().filter_map(|entry| match entry {
+            Entry::Record(record) => Some((file, record)),
+            _ => None,
+        }));
+    }
+    // the sort is stable so messages with the same timestamp keep their order within a file
+    records.sort_by_key(|(_, record)| record.timestamp);
+
+    let mut merged = Vec::new();
+    let mut start = 0;
+    while start < records.len() {
+        let timestamp = records[start].1.timestamp;
+        let end = start
+            + records[start..]
+                .iter()
+                .take_while(|(_, record)| record.timestamp == timestamp)
+                .count();
+        // distinct messages of this timestamp with how often they occur in each file
+        let mut distinct: Vec<(ParsedRecord, Vec<usize>)> = Vec::new();
+        for (file, record) in records[start..end].iter() {
+            let index = match distinct.iter().position(|(known, _)| same_message(known, record)) {
+                Some(index) => index,
+                None => {
+                    distinct.push((record.clone(), vec![0; paths.len()]));
+                    distinct.len() - 1
+                }
+            };
+            let (known, counts) = &mut distinct[index];
+            // only the file of the lowest level has the backtrace
+            if known.backtrace.is_none() {
+                known.backtrace = record.backtrace.clone();
+            }
+            counts[*file] += 1;
+        }
+        for (record, counts) in distinct {
+            let count = counts.into_iter().max().unwrap_or(1);
+            merged.extend(std::iter::repeat_n(record, count));
+        }
+        start = end;
+    }
+    Ok(merged)
+}
+
+fn same_message(a: &ParsedRecord, b: &ParsedRecord) -> bool {
+    a.severity == b.severity
+        && a.verbosity == b.verbosity
+        && a.timestamp == b.timestamp
+        && a.ids == b.ids
+        && a.sequence == b.sequence
+        && a.uptime == b.uptime
+        && a.monotonic_ns == b.monotonic_ns
+        && a.file == b.file
+        && a.line == b.line
+        && a.message == b.message
+}
+
+fn parse_header_time(time: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(time.trim(), "%Y/%m/%d %H:%M:%S").ok()
+}
+
+/// Lines of the backtraces glog writes look like `   0: server::handle`
+fn is_backtrace_frame(line: &str) -> bool {
+    match line.trim_start().split_once(": ") {
+        Some((frame, _)) => !frame.is_empty() && frame.bytes().all(|byte| byte.is_ascii_digit()) && line.starts_with(' '),
+        None => false,
+    }
+}
+
+fn parse_duration(duration: &str) -> Option<Duration> {
+    let mut parts = duration.trim().split(':').map(|part| part.parse::<i64>().ok());
+    let hours = parts.next()??;
+    let minutes = parts.next()??;
+    let seconds = parts.next()??;
+    Some(Duration::hours(hours) + Duration::minutes(minutes) + Duration::seconds(seconds))
+}
+
+/// Parse an uptime like `0:00:01.234567`
+fn parse_uptime(uptime: &str) -> Option<std::time::Duration> {
+    let (hours, rest) = uptime.split_once(':')?;
+    let (minutes, rest) = rest.split_once(':')?;
+    let (seconds, micros) = rest.split_once('.')?;
+    let seconds = hours.parse::<u64>().ok()? * 3600 + minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?;
+    Some(std::time::Duration::from_secs(seconds) + std::time::Duration::from_micros(micros.parse().ok()?))
+}
+
+/// Parse a message line and tell whether its timestamp is absolute, i.e. doesn't miss the year
+///
+/// `date_digits` is the length of the date, if known from the file header.
+fn parse_record_line(
+    line: &str,
+    year: i32,
+    start: Option<NaiveDateTime>,
+    date_digits: Option<usize>,
+) -> Option<(ParsedRecord, bool)> {
+    let mut chars = line.chars();
+    let severity = chars.next().filter(|severity| "TDIWEFV".contains(*severity))?;
+    let rest = chars.as_str();
+    let (verbosity, rest) = if severity == 'V' {
+        // the timestamp follows right after the verbosity, so it's split off the end of the digits
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let timestamp_digits = if rest[digits..].starts_with("ns") {
+            digits.saturating_sub(1)
+        } else {
+            date_digits.unwrap_or(if digits > 8 { 8 } else { 4 })
+        };
+        let verbosity_digits = digits.checked_sub(timestamp_digits).filter(|digits| *digits > 0)?;
+        (Some(rest[..verbosity_digits].parse().ok()?), &rest[verbosity_digits..])
+    } else {
+        (None, rest)
+    };
+
+    let date_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let mut monotonic_ns = None;
+    let (timestamp, absolute, rest) = if rest[date_len..].starts_with("ns") {
+        // only the monotonic time is written
+        let nanos: i64 = rest[..date_len].parse().ok()?;
+        monotonic_ns = Some(nanos as u64);
+        let start = start.unwrap_or_default();
+        (start + Duration::nanoseconds(nanos), true, &rest[date_len + 2..])
+    } else {
+        let (year, month_day) = match date_len {
+            4 => (year, &rest[..4]),
+            8 => (rest[..4].parse().ok()?, &rest[4..8]),
+            _ => return None,
+        };
+        let date = NaiveDate::from_ymd_opt(year, month_day[..2].parse().ok()?, month_day[2..].parse().ok()?)?;
+        let rest = rest[date_len..].strip_prefix(' ')?;
+        let time = rest.get(..15)?;
+        let timestamp = date.and_time(NaiveTime::parse_from_str(time, "%H:%M:%S%.6f").ok()?);
+        (timestamp, date_len == 8, &rest[15..])
+    };
+
+    let (prefix, message) = rest.split_once(']')?;
+    let mut ids = Vec::new();
+    let mut sequence = None;
+    let mut uptime = None;
+    let mut file = None;
+    let mut line_number = None;
+    for token in prefix.split_whitespace() {
+        if let Ok(id) = token.parse() {
+            ids.push(id);
+        } else if let Some(duration) = token.strip_prefix('+') {
+            uptime = Some(parse_uptime(duration)?);
+        } else if let Some(number) = token.strip_prefix('#') {
+            sequence = Some(number.parse().ok()?);
+        } else if let Some(nanos) = token.strip_suffix("ns").and_then(|nanos| nanos.parse().ok()) {
+            monotonic_ns = Some(nanos);
+        } else {
+            let (name, number) = token.rsplit_once(':')?;
+            file = Some(name.to_owned());
+            line_number = Some(number.parse().ok()?);
+        }
+    }
+
+    let record = ParsedRecord {
+        severity,
+        verbosity,
+        timestamp,
+        ids,
+        sequence,
+        uptime,
+        monotonic_ns,
+        file,
+        line: line_number,
+        message: message.strip_prefix(' ').unwrap_or(message).to_owned(),
+        backtrace: None,
+    };
+    Some((record, absolute))
+}
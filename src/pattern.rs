@@ -0,0 +1,518 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// Limit of the compiled size, so counted repetitions can't blow up the memory
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+/// A regular expression matched against the text of log messages
+///
+/// Used to include or exclude messages with [`include_messages`](crate::Glog::include_messages)
+/// and [`exclude_messages`](crate::Glog::exclude_messages). The supported syntax is a subset of
+/// the [`regex`] crate, which covers what's needed to pick out specific lines:
+///
+/// - literal characters, `.` for any character except a newline
+/// - classes like `[a-z_]` or `[^0-9]` and the escapes `\d`, `\w`, `\s`, `\D`, `\W`, `\S`
+/// - `\n`, `\r`, `\t` and escaped punctuation like `\.` or `\[`
+/// - the anchors `^` and `$` for the start and end of the message
+/// - groups `(...)` and `(?:...)`, alternatives `a|b`
+/// - the repetitions `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`, optionally followed by `?`
+/// - a leading `(?i)` to match case insensitively
+///
+/// A pattern matches if it matches anywhere in the message, unless it's anchored. Matching takes
+/// time linear in the length of the message, so even a pathological pattern can't stall logging.
+///
+/// [`regex`]: https://crates.io/crates/regex
+///
+/// # Example
+///
+/// ```
+/// use glog::MessagePattern;
+///
+/// let pattern = MessagePattern::new(r"^connection \d+ (closed|reset)$").unwrap();
+/// assert!(pattern.is_match("connection 42 reset"));
+/// assert!(!pattern.is_match("connection 42 reset by peer"));
+///
+/// let pattern = MessagePattern::new("(?i)timeout").unwrap();
+/// assert!(pattern.is_match("Request TIMEOUT after 5s"));
+///
+/// assert!(MessagePattern::new("unbalanced (group").is_err());
+/// ```
+#[derive(Clone)]
+pub struct MessagePattern {
+    source: String,
+    instructions: Vec<Instruction>,
+    case_insensitive: bool,
+}
+
+/// Why a [`MessagePattern`] couldn't be compiled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pattern: String,
+    reason: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid message pattern {}: {}", self.pattern, self.reason)
+    }
+}
+
+impl Error for PatternError {}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Any,
+    Literal(char),
+    Class { ranges: Vec<(char, char)>, negated: bool },
+}
+
+impl Matcher {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Matcher::Any => c != '\n',
+            Matcher::Literal(literal) => c == *literal,
+            Matcher::Class {
+                ranges,
+                negated,
+            } => ranges.iter().any(|(from, to)| (*from..=*to).contains(&c)) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(Matcher),
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternatives(Vec<Node>),
+    Repeat { node: Box<Node>, min: u32, max: Option<u32> },
+}
+
+#[derive(Debug, Clone)]
+enum Instruction {
+    Char(Matcher),
+    Split(usize, usize),
+    Jump(usize),
+    Start,
+    End,
+    Match,
+}
+
+impl MessagePattern {
+    /// Compile `pattern`
+    ///
+    /// # Errors
+    ///
+    /// Fails if `pattern` isn't valid or uses syntax which isn't supported.
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        let error = |reason: String| PatternError {
+            pattern: pattern.to_owned(),
+            reason,
+        };
+        let (case_insensitive, expression) = match pattern.strip_prefix("(?i)") {
+            Some(expression) => (true, expression),
+            None => (false, pattern),
+        };
+        let mut parser = Parser {
+            chars: expression.chars().collect(),
+            position: 0,
+        };
+        let node = parser.alternatives().map_err(error)?;
+        if parser.position < parser.chars.len() {
+            return Err(error("unmatched )".to_owned()));
+        }
+        let mut instructions = Vec::new();
+        compile(&node, &mut instructions).map_err(error)?;
+        instructions.push(Instruction::Match);
+        Ok(MessagePattern {
+            source: pattern.to_owned(),
+            instructions,
+            case_insensitive,
+        })
+    }
+
+    /// The pattern as it has been given
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        // simulates all paths through the program at once, so every character is looked at once
+        let mut marks = vec![0; self.instructions.len()];
+        let mut mark = 1;
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        let mut chars = text.chars().peekable();
+        let mut at_end = chars.peek().is_none();
+        let mut matched = self.add_thread(&mut current, &mut marks, mark, 0, true, at_end);
+        loop {
+            if matched {
+                return true;
+            }
+            let c = match chars.next() {
+                Some(c) => c,
+                None => return false,
+            };
+            at_end = chars.peek().is_none();
+            mark += 1;
+            next.clear();
+            for pc in current.iter() {
+                if let Instruction::Char(matcher) = &self.instructions[*pc] {
+                    if self.char_matches(matcher, c) {
+                        matched |= self.add_thread(&mut next, &mut marks, mark, pc + 1, false, at_end);
+                    }
+                }
+            }
+            // the match may start at any position
+            matched |= self.add_thread(&mut next, &mut marks, mark, 0, false, at_end);
+            std::mem::swap(&mut current, &mut next);
+        }
+    }
+
+    fn char_matches(&self, matcher: &Matcher, c: char) -> bool {
+        if matcher.matches(c) {
+            return true;
+        }
+        self.case_insensitive
+            && (c.to_lowercase().any(|lower| matcher.matches(lower)) || c.to_uppercase().any(|upper| matcher.matches(upper)))
+    }
+
+    /// Add the thread at `pc` and everything reachable from it without consuming a character
+    ///
+    /// Returns whether the match has been reached.
+    fn add_thread(
+        &self,
+        threads: &mut Vec<usize>,
+        marks: &mut [usize],
+        mark: usize,
+        pc: usize,
+        at_start: bool,
+        at_end: bool,
+    ) -> bool {
+        let mut pending = vec![pc];
+        while let Some(pc) = pending.pop() {
+            if marks[pc] == mark {
+                continue;
+            }
+            marks[pc] = mark;
+            match &self.instructions[pc] {
+                Instruction::Match => return true,
+                Instruction::Jump(target) => pending.push(*target),
+                Instruction::Split(first, second) => {
+                    pending.push(*second);
+                    pending.push(*first);
+                }
+                Instruction::Start if at_start => pending.push(pc + 1),
+                Instruction::End if at_end => pending.push(pc + 1),
+                Instruction::Start | Instruction::End => {}
+                Instruction::Char(_) => threads.push(pc),
+            }
+        }
+        false
+    }
+}
+
+impl FromStr for MessagePattern {
+    type Err = PatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        MessagePattern::new(pattern)
+    }
+}
+
+impl fmt::Debug for MessagePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessagePattern").field(&self.source).finish()
+    }
+}
+
+impl fmt::Display for MessagePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternatives(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.concat()?];
+        while self.eat('|') {
+            alternatives.push(self.concat()?);
+        }
+        Ok(match alternatives.len() {
+            1 => alternatives.pop().unwrap(),
+            _ => Node::Alternatives(alternatives),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.repetition(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.next().unwrap() {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err("only (?:...) groups and a leading (?i) are supported".to_owned());
+                }
+                let node = self.alternatives()?;
+                if !self.eat(')') {
+                    return Err("unclosed group".to_owned());
+                }
+                Ok(node)
+            }
+            '[' => self.class(),
+            '.' => Ok(Node::Char(Matcher::Any)),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '\\' => self.escape(),
+            c @ ('*' | '+' | '?' | '{') => Err(format!("{} without anything to repeat", c)),
+            c => Ok(Node::Char(Matcher::Literal(c))),
+        }
+    }
+
+    fn repetition(&mut self, mut node: Node) -> Result<Node, String> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    self.position += 1;
+                    self.counted()?
+                }
+                _ => return Ok(node),
+            };
+            self.position += 1;
+            // only whether there is a match counts, so lazy repetitions behave like greedy ones
+            self.eat('?');
+            if matches!(node, Node::Start | Node::End | Node::Empty) {
+                return Err("repetition of an empty expression".to_owned());
+            }
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    /// Parse the bounds of `{n}`, `{n,}` or `{n,m}` after the `{` and stop at the `}`
+    fn counted(&mut self) -> Result<(u32, Option<u32>), String> {
+        let min = self.number()?;
+        let max = if self.eat(',') {
+            match self.peek() {
+                Some('}') => None,
+                _ => Some(self.number()?),
+            }
+        } else {
+            Some(min)
+        };
+        if self.peek() != Some('}') {
+            return Err("unclosed repetition".to_owned());
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("repetition with a maximum below its minimum {}", min));
+        }
+        Ok((min, max))
+    }
+
+    fn number(&mut self) -> Result<u32, String> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .map_err(|_| "expected the number of repetitions".to_owned())
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self.next().ok_or_else(|| "trailing \\".to_owned())?;
+        if let Some((ranges, negated)) = perl_class(c) {
+            return Ok(Node::Char(Matcher::Class {
+                ranges,
+                negated,
+            }));
+        }
+        Ok(Node::Char(Matcher::Literal(escaped_literal(c)?)))
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.next().ok_or_else(|| "unclosed class".to_owned())?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let from = if c == '\\' {
+                let escaped = self.next().ok_or_else(|| "unclosed class".to_owned())?;
+                match perl_class(escaped) {
+                    Some((perl_ranges, false)) => {
+                        ranges.extend(perl_ranges);
+                        continue;
+                    }
+                    Some((_, true)) => return Err(format!("\\{} isn't supported in a class", escaped)),
+                    None => escaped_literal(escaped)?,
+                }
+            } else {
+                c
+            };
+            if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and(|c| *c != ']') {
+                self.position += 1;
+                let to = match self.next().unwrap() {
+                    '\\' => escaped_literal(self.next().ok_or_else(|| "unclosed class".to_owned())?)?,
+                    to => to,
+                };
+                if to < from {
+                    return Err(format!("invalid range {}-{}", from, to));
+                }
+                ranges.push((from, to));
+            } else {
+                ranges.push((from, from));
+            }
+        }
+        Ok(Node::Char(Matcher::Class {
+            ranges,
+            negated,
+        }))
+    }
+}
+
+/// The ranges of `\d`, `\w` and `\s` and whether they are negated like `\D`, `\W` and `\S`
+fn perl_class(c: char) -> Option<(Vec<(char, char)>, bool)> {
+    let ranges = match c.to_ascii_lowercase() {
+        'd' => vec![('0', '9')],
+        'w' => vec![('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+        's' => vec![('\t', '\r'), (' ', ' ')],
+        _ => return None,
+    };
+    Some((ranges, c.is_ascii_uppercase()))
+}
+
+fn escaped_literal(c: char) -> Result<char, String> {
+    match c {
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        c if c.is_ascii_punctuation() || c == ' ' => Ok(c),
+        c => Err(format!("unsupported escape \\{}", c)),
+    }
+}
+
+fn compile(node: &Node, instructions: &mut Vec<Instruction>) -> Result<(), String> {
+    if instructions.len() > MAX_INSTRUCTIONS {
+        return Err("pattern too large".to_owned());
+    }
+    match node {
+        Node::Empty => {}
+        Node::Char(matcher) => instructions.push(Instruction::Char(matcher.clone())),
+        Node::Start => instructions.push(Instruction::Start),
+        Node::End => instructions.push(Instruction::End),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, instructions)?;
+            }
+        }
+        Node::Alternatives(alternatives) => {
+            let mut jumps = Vec::new();
+            for (i, alternative) in alternatives.iter().enumerate() {
+                if i + 1 < alternatives.len() {
+                    let split = instructions.len();
+                    instructions.push(Instruction::Split(split + 1, 0));
+                    compile(alternative, instructions)?;
+                    jumps.push(instructions.len());
+                    instructions.push(Instruction::Jump(0));
+                    let next = instructions.len();
+                    instructions[split] = Instruction::Split(split + 1, next);
+                } else {
+                    compile(alternative, instructions)?;
+                }
+            }
+            let end = instructions.len();
+            for jump in jumps {
+                instructions[jump] = Instruction::Jump(end);
+            }
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+        } => {
+            for _ in 0..*min {
+                compile(node, instructions)?;
+            }
+            match max {
+                None => {
+                    let split = instructions.len();
+                    instructions.push(Instruction::Split(split + 1, 0));
+                    compile(node, instructions)?;
+                    instructions.push(Instruction::Jump(split));
+                    let end = instructions.len();
+                    instructions[split] = Instruction::Split(split + 1, end);
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(instructions.len());
+                        instructions.push(Instruction::Split(0, 0));
+                        compile(node, instructions)?;
+                        if instructions.len() > MAX_INSTRUCTIONS {
+                            return Err("pattern too large".to_owned());
+                        }
+                    }
+                    let end = instructions.len();
+                    for split in splits {
+                        instructions[split] = Instruction::Split(split + 1, end);
+                    }
+                }
+            }
+        }
+    }
+    if instructions.len() > MAX_INSTRUCTIONS {
+        return Err("pattern too large".to_owned());
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! Convenience re-exports to log with glog using a single import
+//!
+//! Besides the glog types this contains the logging macros of the [`standard logging`] frontend.
+//!
+//! [`standard logging`]: https://crates.io/crates/log
+//!
+//! # Example
+//!
+//! ```
+//! use glog::prelude::*;
+//!
+//! glog::new().init(Flags {
+//!         minloglevel: Level::Warn,
+//!         logtostderr: true,
+//!         ..Default::default()
+//!     }).unwrap();
+//!
+//! warn!("Only a single import needed");
+//! ```
+
+pub use log::{debug, error, info, log, log_enabled, trace, warn, Level, LevelFilter};
+
+pub use crate::{
+    check_err,
+    counter,
+    fatal,
+    log_at,
+    log_caller,
+    log_duration,
+    log_error,
+    log_every_n,
+    log_first_n,
+    log_sysresult,
+    log_with_location,
+    qfatal,
+    vlog,
+    vlog_is_on,
+    Flags,
+    GlogError,
+};
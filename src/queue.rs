@@ -0,0 +1,168 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle, ThreadId},
+    time::{Duration, Instant},
+};
+
+use log::Level;
+
+use crate::Backpressure;
+
+/// A formatted message for the log files which is written on the writer thread
+pub(crate) struct FileMessage {
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) message: String,
+    pub(crate) backtrace: Option<String>,
+}
+
+struct State {
+    messages: VecDeque<FileMessage>,
+    writing: bool,
+    closed: bool,
+}
+
+/// Bounded queue between the logging threads and the thread writing the log files
+pub(crate) struct WriteQueue {
+    capacity: usize,
+    state: Mutex<State>,
+    pushed: Condvar,
+    popped: Condvar,
+    writer: Mutex<Option<ThreadId>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    // when the dropped messages have been reported and how many were dropped per level by then
+    last_report: Mutex<(Instant, [u64; 5])>,
+}
+
+impl WriteQueue {
+    pub(crate) fn new(capacity: usize) -> Self {
+        WriteQueue {
+            capacity,
+            state: Mutex::new(State {
+                messages: VecDeque::with_capacity(capacity),
+                writing: false,
+                closed: false,
+            }),
+            pushed: Condvar::new(),
+            popped: Condvar::new(),
+            writer: Mutex::new(None),
+            thread: Mutex::new(None),
+            last_report: Mutex::new((Instant::now(), [0; 5])),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Whether the calling thread is the writer thread, which has to write synchronously
+    pub(crate) fn is_writer(&self) -> bool {
+        *self.writer.lock().unwrap() == Some(thread::current().id())
+    }
+
+    /// Queue `message` and return the message dropped to make room for it, if any
+    ///
+    /// Fails with `message` if the queue has been [closed](WriteQueue::close), the caller has to
+    /// write it itself.
+    pub(crate) fn push(&self, message: FileMessage, backpressure: Backpressure) -> Result<Option<FileMessage>, FileMessage> {
+        let mut state = self.state.lock().unwrap();
+        let mut dropped = None;
+        if state.messages.len() >= self.capacity && !state.closed {
+            match backpressure {
+                Backpressure::Block => {
+                    while state.messages.len() >= self.capacity && !state.closed {
+                        state = self.popped.wait(state).unwrap();
+                    }
+                }
+                Backpressure::Drop => return Ok(Some(message)),
+                Backpressure::DropOldest => dropped = state.messages.pop_front(),
+            }
+        }
+        if state.closed {
+            return Err(message);
+        }
+        state.messages.push_back(message);
+        self.pushed.notify_one();
+        Ok(dropped)
+    }
+
+    /// The messages dropped per level since the last report if they should be reported now
+    ///
+    /// `dropped` are the messages dropped per level so far. A report is due once `interval` has
+    /// passed since the last one, or right away if `force` is set.
+    pub(crate) fn due_report(&self, dropped: [u64; 5], interval: Duration, force: bool) -> Option<[u64; 5]> {
+        let mut last_report = self.last_report.lock().unwrap();
+        let (reported_at, reported) = *last_report;
+        let mut since = [0; 5];
+        for (since, (dropped, reported)) in since.iter_mut().zip(dropped.iter().zip(reported.iter())) {
+            *since = dropped - reported;
+        }
+        if since.iter().all(|dropped| *dropped == 0) || (!force && reported_at.elapsed() < interval) {
+            return None;
+        }
+        *last_report = (Instant::now(), dropped);
+        Some(since)
+    }
+
+    /// Wait until every queued message has been written
+    pub(crate) fn drain(&self) {
+        // the writer can't wait for itself
+        if self.is_writer() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        while !state.messages.is_empty() || state.writing {
+            state = self.popped.wait(state).unwrap();
+        }
+    }
+
+    /// Forget the messages queued by the parent process and its writer thread after a fork
+    pub(crate) fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.messages.clear();
+        state.writing = false;
+        state.closed = false;
+        *self.writer.lock().unwrap() = None;
+    }
+
+    /// Stop accepting messages and wait until the writer thread has written the queued ones
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.pushed.notify_all();
+        self.popped.notify_all();
+        if self.is_writer() {
+            return;
+        }
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+
+    /// Write the queued messages with `write` on a background thread until the queue is closed
+    pub(crate) fn spawn<F: Fn(FileMessage) + Send + 'static>(self: Arc<Self>, write: F) -> io::Result<()> {
+        let queue = self.clone();
+        let thread = thread::Builder::new().name("glog-writer".to_owned()).spawn(move || {
+            *queue.writer.lock().unwrap() = Some(thread::current().id());
+            loop {
+                let message = {
+                    let mut state = queue.state.lock().unwrap();
+                    while state.messages.is_empty() {
+                        if state.closed {
+                            return;
+                        }
+                        state = queue.pushed.wait(state).unwrap();
+                    }
+                    state.writing = true;
+                    state.messages.pop_front().unwrap()
+                };
+                write(message);
+                queue.state.lock().unwrap().writing = false;
+                queue.popped.notify_all();
+            }
+        })?;
+        *self.thread.lock().unwrap() = Some(thread);
+        Ok(())
+    }
+}
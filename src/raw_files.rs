@@ -0,0 +1,76 @@
+use std::{
+    fs::File,
+    mem::ManuallyDrop,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use log::Level;
+
+const NO_FILE: i64 = -1;
+
+// Raw descriptors of the open log files indexed by level so they can be accessed without locking
+static RAW_LOG_FILES: [AtomicI64; 5] = [const { AtomicI64::new(NO_FILE) }; 5];
+
+fn index(level: Level) -> usize {
+    level as usize - 1
+}
+
+#[cfg(target_family = "unix")]
+fn to_raw(file: &File) -> i64 {
+    use std::os::unix::io::AsRawFd;
+    file.as_raw_fd().into()
+}
+
+#[cfg(target_family = "unix")]
+fn from_raw(raw: i64) -> ManuallyDrop<File> {
+    use std::os::unix::io::FromRawFd;
+    // SAFETY: the descriptor stays open until it is unregistered and ManuallyDrop keeps it from being closed here
+    ManuallyDrop::new(unsafe { File::from_raw_fd(raw as i32) })
+}
+
+#[cfg(target_family = "windows")]
+fn to_raw(file: &File) -> i64 {
+    use std::os::windows::io::AsRawHandle;
+    file.as_raw_handle() as isize as i64
+}
+
+#[cfg(target_family = "windows")]
+fn from_raw(raw: i64) -> ManuallyDrop<File> {
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+    // SAFETY: the handle stays open until it is unregistered and ManuallyDrop keeps it from being closed here
+    ManuallyDrop::new(unsafe { File::from_raw_handle(raw as isize as RawHandle) })
+}
+
+pub(crate) fn register(level: Level, file: &File) {
+    RAW_LOG_FILES[index(level)].store(to_raw(file), Ordering::SeqCst);
+}
+
+pub(crate) fn unregister(level: Level) {
+    RAW_LOG_FILES[index(level)].store(NO_FILE, Ordering::SeqCst);
+}
+
+/// Flush the log files without taking any locks
+///
+/// This is the counterpart to `FlushLogFilesUnsafe` in glog and is meant to be used from
+/// signal handlers for failures, where the crashed thread may still hold the locks which are
+/// used by [`flush`](crate::flush). The files are synced to disk directly through their
+/// descriptors.
+///
+/// # Example
+///
+/// ```
+/// use glog::Flags;
+///
+/// glog::new().init(Flags::default()).unwrap();
+///
+/// // Inside a failure signal handler
+/// glog::flush_log_files_unsafe();
+/// ```
+pub fn flush_log_files_unsafe() {
+    for raw in &RAW_LOG_FILES {
+        let raw = raw.load(Ordering::SeqCst);
+        if raw != NO_FILE {
+            let _ = from_raw(raw).sync_data();
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::{
+    cell::Cell,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+thread_local! {
+    static STATE: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Randomly decide whether to keep a message sampled at 1 in `n`
+pub(crate) fn keep(n: u32) -> bool {
+    if n <= 1 {
+        return true;
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        if x == 0 {
+            // seed each thread differently, the quality of a xorshift generator is plenty for sampling
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default();
+            x = (nanos ^ (state as *const Cell<u64> as u64)) | 1;
+        }
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d) % u64::from(n) == 0
+    })
+}
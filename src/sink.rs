@@ -0,0 +1,121 @@
+use std::{sync::RwLock, thread};
+
+use chrono::{DateTime, Local};
+use log::{
+    kv::{self, Key, Value, VisitSource},
+    Level,
+    Record,
+};
+
+static SINKS: RwLock<Vec<Box<dyn Sink>>> = RwLock::new(Vec::new());
+
+/// Everything known about a log message
+///
+/// Passed to each [`Sink`] so it can format the message in whatever way it needs.
+#[derive(Debug, Clone)]
+pub struct RecordData {
+    /// Level the message was logged at
+    pub level: Level,
+    /// Target of the message, the module path by default
+    pub target: String,
+    /// Module the message was logged in
+    pub module_path: Option<String>,
+    /// Source file of the log invocation
+    pub file: Option<String>,
+    /// Line of the log invocation
+    pub line: Option<u32>,
+    /// Name of the logging thread
+    pub thread_name: Option<String>,
+    /// Id of the logging thread as shown in the prefix
+    pub thread_id: u64,
+    /// Time at which the message was logged
+    pub timestamp: DateTime<Local>,
+    /// The message text after redaction and truncation
+    pub message: String,
+    /// Key value pairs attached to the message with the `key = value;` syntax of the logging macros
+    pub key_values: Vec<(String, String)>,
+}
+
+impl RecordData {
+    pub(crate) fn new(record: &Record, thread_id: u64, message: String, timestamp: DateTime<Local>) -> Self {
+        let mut key_values = KeyValues(Vec::new());
+        let _ = record.key_values().visit(&mut key_values);
+        RecordData {
+            level: record.level(),
+            target: record.target().to_owned(),
+            module_path: record.module_path().map(str::to_owned),
+            file: record.file().map(str::to_owned),
+            line: record.line(),
+            thread_name: thread::current().name().map(str::to_owned),
+            thread_id,
+            timestamp,
+            message,
+            key_values: key_values.0,
+        }
+    }
+}
+
+struct KeyValues(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for KeyValues {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+/// A destination which receives every log message with its full context
+///
+/// Unlike [`add_writer`](crate::add_writer) a sink isn't limited to the glog text format.
+/// Register one with [`add_sink`].
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{Flags, RecordData, Sink};
+///
+/// struct Json;
+///
+/// impl Sink for Json {
+///     fn log(&self, record: &RecordData) {
+///         println!(
+///             r#"{{"level":"{}","target":"{}","message":{:?}}}"#,
+///             record.level, record.target, record.message
+///         );
+///     }
+/// }
+///
+/// glog::new().init(Flags::default()).unwrap();
+/// glog::add_sink(Box::new(Json));
+///
+/// info!(request_id = 42; "Handled request");
+/// ```
+pub trait Sink: Send + Sync {
+    /// Write a single message
+    fn log(&self, record: &RecordData);
+
+    /// Flush any buffered messages
+    fn flush(&self) {}
+}
+
+/// Send all messages to `sink` in addition to the other destinations
+pub fn add_sink(sink: Box<dyn Sink>) {
+    SINKS.write().unwrap().push(sink);
+}
+
+pub(crate) fn has_sinks() -> bool {
+    !SINKS.read().unwrap().is_empty()
+}
+
+pub(crate) fn write(record: &RecordData) {
+    for sink in SINKS.read().unwrap().iter() {
+        sink.log(record);
+    }
+}
+
+pub(crate) fn flush() {
+    for sink in SINKS.read().unwrap().iter() {
+        sink.flush();
+    }
+}
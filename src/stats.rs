@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::Level;
+
+const LEVELS: [Level; 5] = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+
+/// Logging statistics of a single level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelStats {
+    /// Messages which have been logged
+    pub emitted: u64,
+    /// Messages which reached glog but have been dropped by its filters
+    ///
+    /// Counts the messages below [`minloglevel`](crate::Flags::minloglevel), of blocked or not
+    /// allowed targets, above the [verbosity](crate::Flags::v), left out by
+    /// [sampling](crate::Glog::sample_level), rejected by [`with_filter`](crate::Glog::with_filter) and
+    /// filtered by their [text](crate::Glog::exclude_messages).
+    pub suppressed: u64,
+    /// Bytes written to the log files
+    pub bytes_written: u64,
+    /// Messages which never reached the log files because the queue of the background writer was
+    /// full, see [`backpressure`](crate::Flags::backpressure)
+    pub dropped: u64,
+}
+
+/// Logging statistics per level since glog has been initialized
+///
+/// Returned by [`stats`](crate::stats()).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogStats {
+    levels: [LevelStats; 5],
+    write_errors: u64,
+}
+
+impl LogStats {
+    /// The statistics of messages logged at `level`
+    pub fn level(&self, level: Level) -> LevelStats {
+        self.levels[index(level)]
+    }
+
+    /// Number of times writing to a log destination failed
+    pub fn write_errors(&self) -> u64 {
+        self.write_errors
+    }
+
+    /// The statistics of all levels combined
+    pub fn total(&self) -> LevelStats {
+        self.levels.iter().fold(LevelStats::default(), |total, level| LevelStats {
+            emitted: total.emitted + level.emitted,
+            suppressed: total.suppressed + level.suppressed,
+            bytes_written: total.bytes_written + level.bytes_written,
+            dropped: total.dropped + level.dropped,
+        })
+    }
+
+    /// Render the statistics in the Prometheus text exposition format
+    ///
+    /// The counters aren't registered with the `prometheus` or `metrics` crates. Serve the result on
+    /// a metrics endpoint instead, e.g. appended to the output of the registry of the application.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use log::*;
+    /// use glog::Flags;
+    ///
+    /// glog::new().init(Flags::default()).unwrap();
+    ///
+    /// warn!("Counted");
+    ///
+    /// let metrics = glog::stats().to_prometheus();
+    /// assert!(metrics.contains("glog_messages_total{level=\"WARN\"} 1\n"));
+    /// assert!(metrics.contains("glog_suppressed_total "));
+    /// ```
+    pub fn to_prometheus(&self) -> String {
+        let mut metrics = String::new();
+        metrics.push_str("# HELP glog_messages_total Messages logged per level.\n");
+        metrics.push_str("# TYPE glog_messages_total counter\n");
+        for level in LEVELS {
+            metrics.push_str(&format!(
+                "glog_messages_total{{level=\"{}\"}} {}\n",
+                level.as_str(),
+                self.level(level).emitted
+            ));
+        }
+        metrics.push_str("# HELP glog_bytes_written_total Bytes written to the log files per level.\n");
+        metrics.push_str("# TYPE glog_bytes_written_total counter\n");
+        for level in LEVELS {
+            metrics.push_str(&format!(
+                "glog_bytes_written_total{{level=\"{}\"}} {}\n",
+                level.as_str(),
+                self.level(level).bytes_written
+            ));
+        }
+        metrics.push_str(
+            "# HELP glog_suppressed_total Messages dropped by the level, target, verbosity, sampling, custom or message filters.\n",
+        );
+        metrics.push_str("# TYPE glog_suppressed_total counter\n");
+        metrics.push_str(&format!("glog_suppressed_total {}\n", self.total().suppressed));
+        metrics.push_str("# HELP glog_queue_dropped_total Messages dropped because the writer queue was full.\n");
+        metrics.push_str("# TYPE glog_queue_dropped_total counter\n");
+        for level in LEVELS {
+            metrics.push_str(&format!(
+                "glog_queue_dropped_total{{level=\"{}\"}} {}\n",
+                level.as_str(),
+                self.level(level).dropped
+            ));
+        }
+        metrics.push_str("# HELP glog_write_errors_total Failed writes to log destinations.\n");
+        metrics.push_str("# TYPE glog_write_errors_total counter\n");
+        metrics.push_str(&format!("glog_write_errors_total {}\n", self.write_errors));
+        metrics
+    }
+}
+
+fn index(level: Level) -> usize {
+    level as usize - 1
+}
+
+#[derive(Default)]
+struct LevelCounters {
+    emitted: AtomicU64,
+    suppressed: AtomicU64,
+    bytes_written: AtomicU64,
+    dropped: AtomicU64,
+}
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    levels: [LevelCounters; 5],
+    write_errors: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn emitted(&self, level: Level) {
+        self.levels[index(level)].emitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn suppressed(&self, level: Level) {
+        self.levels[index(level)].suppressed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn written(&self, level: Level, bytes: usize) {
+        self.levels[index(level)]
+            .bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dropped(&self, level: Level) {
+        self.levels[index(level)].dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn write_error(&self) {
+        self.write_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LogStats {
+        let mut stats = LogStats::default();
+        for level in LEVELS {
+            let counters = &self.levels[index(level)];
+            stats.levels[index(level)] = LevelStats {
+                emitted: counters.emitted.load(Ordering::Relaxed),
+                suppressed: counters.suppressed.load(Ordering::Relaxed),
+                bytes_written: counters.bytes_written.load(Ordering::Relaxed),
+                dropped: counters.dropped.load(Ordering::Relaxed),
+            };
+        }
+        stats.write_errors = self.write_errors.load(Ordering::Relaxed);
+        stats
+    }
+}
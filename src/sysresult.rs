@@ -0,0 +1,83 @@
+use std::{fmt, io};
+
+/// Renders a Windows `HRESULT` or `GetLastError` code with its system message
+///
+/// The message is looked up with `FormatMessageW` by the standard library, e.g.
+/// `The system cannot find the file specified. (os error -2147024894) [0x80070002]`. On other
+/// platforms the code is taken as an `errno` value. Used by [`log_sysresult!`](crate::log_sysresult).
+///
+/// An `HRESULT` has failed if it is negative, any other code has failed unless it is 0.
+///
+/// # Example
+///
+/// ```
+/// use log::*;
+/// use glog::{Flags, SysResult};
+///
+/// glog::new().init(Flags {
+///         logtostderr: true,
+///         ..Default::default()
+///     }).unwrap();
+///
+/// // after a failed call of the Windows API
+/// error!("CreateFileW failed: {}", SysResult::last_error());
+///
+/// assert!(SysResult::hresult(0x80070005_u32 as i32).failed());
+/// assert!(!SysResult::hresult(1).failed());
+/// assert!(SysResult::error_code(5).failed());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SysResult {
+    code: i32,
+    hresult: bool,
+}
+
+impl SysResult {
+    /// An `HRESULT` returned by a COM call or another API of Windows
+    pub fn hresult(code: i32) -> Self {
+        SysResult {
+            code,
+            hresult: true,
+        }
+    }
+
+    /// A `GetLastError` code on Windows or an `errno` value elsewhere
+    pub fn error_code(code: i32) -> Self {
+        SysResult {
+            code,
+            hresult: false,
+        }
+    }
+
+    /// The last error of the calling thread, `GetLastError` on Windows and `errno` elsewhere
+    pub fn last_error() -> Self {
+        SysResult::error_code(io::Error::last_os_error().raw_os_error().unwrap_or(0))
+    }
+
+    /// The raw code
+    pub fn code(self) -> i32 {
+        self.code
+    }
+
+    /// Whether the code indicates a failure, for `HRESULT`s the counterpart to the `FAILED` macro
+    pub fn failed(self) -> bool {
+        if self.hresult {
+            self.code < 0
+        } else {
+            self.code != 0
+        }
+    }
+}
+
+/// Integers are taken as `HRESULT`s
+impl From<i32> for SysResult {
+    fn from(code: i32) -> Self {
+        SysResult::hresult(code)
+    }
+}
+
+impl fmt::Display for SysResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{:#010x}]", io::Error::from_raw_os_error(self.code), self.code as u32)
+    }
+}
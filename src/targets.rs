@@ -0,0 +1,61 @@
+use std::{collections::HashMap, sync::RwLock};
+
+/// Targets of which the answer is cached, beyond that targets are matched every time
+const CACHE_SIZE: usize = 1024;
+
+/// Whether `target` is `prefix` or one of its submodules
+pub(crate) fn matches_target(target: &str, prefix: &str) -> bool {
+    target
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// A list of targets which also covers their submodules
+///
+/// The answer for each target is cached, as most targets are module paths which are checked for
+/// every message.
+#[derive(Debug, Default)]
+pub(crate) struct TargetList {
+    targets: Vec<String>,
+    cache: RwLock<HashMap<String, bool>>,
+}
+
+impl TargetList {
+    pub(crate) fn push(&mut self, target: &str) {
+        self.targets.push(target.to_owned());
+        self.cache.get_mut().unwrap().clear();
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    pub(crate) fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Whether `target` is one of the targets or a submodule of one
+    pub(crate) fn contains(&self, target: &str) -> bool {
+        if self.targets.is_empty() {
+            return false;
+        }
+        if let Some(contained) = self.cache.read().unwrap().get(target) {
+            return *contained;
+        }
+        let contained = self.targets.iter().any(|prefix| matches_target(target, prefix));
+        let mut cache = self.cache.write().unwrap();
+        if cache.len() < CACHE_SIZE {
+            cache.insert(target.to_owned(), contained);
+        }
+        contained
+    }
+}
+
+impl Clone for TargetList {
+    fn clone(&self) -> Self {
+        TargetList {
+            targets: self.targets.clone(),
+            cache: RwLock::new(self.cache.read().unwrap().clone()),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::{ffi::c_void, fmt, path::Path};
+
+use backtrace::{Backtrace, BacktraceFmt, BacktraceFrame, BytesOrWideString, PrintFmt};
+
+/// Crates whose frames are at the top of every backtrace logged by glog
+const LOGGER_FRAMES: [&str; 6] = ["backtrace::", "<backtrace::", "glog::", "<glog::", "log::", "<log::"];
+
+/// Frames of the standard library between a panic and its short backtrace marker
+const PANIC_FRAMES: [&str; 4] = ["std::panicking::", "core::panicking::", "__rustc::", "rust_begin_unwind"];
+
+/// Frames of the standard library delimiting the frames of the application, see `std::backtrace`
+const SHORT_BACKTRACE_END: &str = "__rust_end_short_backtrace";
+const SHORT_BACKTRACE_BEGIN: &str = "__rust_begin_short_backtrace";
+
+/// How backtraces are captured and rendered
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BacktraceOptions {
+    pub(crate) resolve: bool,
+    pub(crate) depth: Option<usize>,
+    pub(crate) short: bool,
+}
+
+impl Default for BacktraceOptions {
+    fn default() -> Self {
+        BacktraceOptions {
+            resolve: true,
+            depth: None,
+            short: false,
+        }
+    }
+}
+
+/// Capture a backtrace of the calling thread and render it for the log
+///
+/// Without `resolve` only the addresses of the frames are rendered, relative to the module they
+/// belong to if it is known, which can be turned into symbols later with [`symbolicate`]. Resolved
+/// backtraces start at the log invocation, unresolved ones can't tell the frames of the logger apart.
+/// Short backtraces render one line per frame and leave out the frames of the runtime around the
+/// application, like `RUST_BACKTRACE=1` does with the backtraces of panics.
+pub(crate) fn capture(options: BacktraceOptions) -> String {
+    capture_below(options, false)
+}
+
+/// Capture a backtrace in a panic hook, starting at the panic
+pub(crate) fn capture_panic(options: BacktraceOptions) -> String {
+    capture_below(options, true)
+}
+
+/// Capture a backtrace skipping the frames of the logger, or of the panic machinery for a `panic`
+fn capture_below(options: BacktraceOptions, panic: bool) -> String {
+    let backtrace = if options.resolve {
+        Backtrace::new()
+    } else {
+        Backtrace::new_unresolved()
+    };
+    let mut frames = backtrace.frames();
+    if options.resolve {
+        if panic {
+            if let Some(end) = frames.iter().rposition(|frame| has_symbol(frame, SHORT_BACKTRACE_END)) {
+                frames = &frames[end + 1..];
+            }
+        }
+        let skip: &[&str] = if panic { &PANIC_FRAMES } else { &[] };
+        let logger_frames = frames
+            .iter()
+            .take_while(|frame| is_frame_of(frame, &LOGGER_FRAMES) || is_frame_of(frame, skip))
+            .count();
+        // keep everything if the symbols are missing
+        if logger_frames < frames.len() {
+            frames = &frames[logger_frames..];
+        }
+        if options.short {
+            frames = short_frames(frames);
+        }
+    }
+    let depth = options.depth.map_or(frames.len(), |depth| depth.min(frames.len()));
+    let mut rendered = if options.resolve && options.short {
+        short_lines(&frames[..depth])
+    } else if options.resolve {
+        Frames(&frames[..depth]).to_string().trim_end().to_owned()
+    } else {
+        frames[..depth]
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| match module_of(frame.ip()) {
+                Some((module, base)) => format!("{:4}: {:p} - {}+{:#x}", i, frame.ip(), module, frame.ip() as usize - base),
+                None => format!("{:4}: {:p}", i, frame.ip()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if depth < frames.len() {
+        rendered.push_str(&format!("\n      ... {} more frames", frames.len() - depth));
+    }
+    rendered
+}
+
+/// Whether all symbols of `frame` start with one of `prefixes`
+///
+/// The names are compared without the hashes of the symbols, which the symbols of the standard
+/// library contain for each crate, e.g. `core[c1f1a4ba060b9bfa]::panicking::panic_fmt`.
+fn is_frame_of(frame: &BacktraceFrame, prefixes: &[&str]) -> bool {
+    !frame.symbols().is_empty()
+        && frame.symbols().iter().all(|symbol| {
+            symbol
+                .name()
+                .is_some_and(|name| prefixes.iter().any(|prefix| format!("{:#}", name).starts_with(prefix)))
+        })
+}
+
+/// The frames between the markers of the standard library, or all frames without markers
+fn short_frames(frames: &[BacktraceFrame]) -> &[BacktraceFrame] {
+    let start = frames
+        .iter()
+        .rposition(|frame| has_symbol(frame, SHORT_BACKTRACE_END))
+        .map_or(0, |end| end + 1);
+    let frames = &frames[start..];
+    match frames.iter().position(|frame| has_symbol(frame, SHORT_BACKTRACE_BEGIN)) {
+        // keep the frames of the thread's entry point if that is all there is
+        Some(begin) if begin > 0 => &frames[..begin],
+        _ => frames,
+    }
+}
+
+fn has_symbol(frame: &BacktraceFrame, marker: &str) -> bool {
+    frame
+        .symbols()
+        .iter()
+        .any(|symbol| symbol.name().is_some_and(|name| format!("{:#}", name).contains(marker)))
+}
+
+/// One line per frame like `   0: server::handle at src/server.rs:42`
+///
+/// Frames inlined into each other share a line, innermost first, separated by ` <- `.
+fn short_lines(frames: &[BacktraceFrame]) -> String {
+    let cwd = std::env::current_dir().ok();
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let symbols = frame
+                .symbols()
+                .iter()
+                .map(|symbol| {
+                    // the alternate format leaves out the hash of the symbol
+                    let mut rendered = symbol
+                        .name()
+                        .map(|name| format!("{:#}", name))
+                        .unwrap_or_else(|| "<unknown>".to_owned());
+                    if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                        let file = cwd.as_ref().and_then(|cwd| file.strip_prefix(cwd).ok()).unwrap_or(file);
+                        rendered.push_str(&format!(" at {}:{}", file.display(), line));
+                    }
+                    rendered
+                })
+                .collect::<Vec<_>>();
+            if symbols.is_empty() {
+                format!("{:4}: {:p}", i, frame.ip())
+            } else {
+                format!("{:4}: {}", i, symbols.join(" <- "))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolved frames rendered like the `Debug` output of [`Backtrace`]
+struct Frames<'a>(&'a [BacktraceFrame]);
+
+impl fmt::Display for Frames<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cwd = std::env::current_dir();
+        // paths below the working directory are shown relative to it
+        let mut print_path = move |f: &mut fmt::Formatter<'_>, path: BytesOrWideString<'_>| {
+            let path = path.into_path_buf();
+            match cwd.as_ref().ok().and_then(|cwd| path.strip_prefix(cwd).ok()) {
+                Some(relative) => fmt::Display::fmt(&relative.display(), f),
+                None => fmt::Display::fmt(&path.display(), f),
+            }
+        };
+        let mut backtrace = BacktraceFmt::new(f, PrintFmt::Short, &mut print_path);
+        backtrace.add_context()?;
+        for frame in self.0 {
+            backtrace.frame().backtrace_frame(frame)?;
+        }
+        backtrace.finish()
+    }
+}
+
+/// Resolve the symbols of a backtrace which has been logged without resolving it
+///
+/// Frames logged with [`resolve_backtraces(false)`](crate::Glog::resolve_backtraces) only carry
+/// their address relative to the executable or library they belong to. This replaces the frames
+/// of the executable calling it with their function names and source locations, so it has to be
+/// called by the same build of the application which logged the backtrace, e.g. from a
+/// `--symbolicate` mode of the application. All other lines are returned unchanged.
+///
+/// The offsets can also be resolved with external tools, e.g. `addr2line -f -C -e <executable> <offset>`.
+///
+/// # Example
+///
+/// ```no_run
+/// let log = std::fs::read_to_string("/tmp/my_app.INFO").unwrap();
+/// println!("{}", glog::symbolicate(&log));
+/// ```
+pub fn symbolicate(text: &str) -> String {
+    let executable = module_of(symbolicate as *mut c_void);
+    let mut symbolicated = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let frame = match (&executable, parse_frame(line.trim_end())) {
+            // the executable may have been started through another path
+            (Some((executable, base)), Some((index, module, offset)))
+                if Path::new(module).file_name() == Path::new(executable).file_name() =>
+            {
+                resolve(index, base + offset)
+            }
+            _ => None,
+        };
+        match frame {
+            Some(frame) => {
+                symbolicated.push_str(&frame);
+                if line.ends_with('\n') {
+                    symbolicated.push('\n');
+                }
+            }
+            None => symbolicated.push_str(line),
+        }
+    }
+    symbolicated
+}
+
+/// Split an unresolved frame like `   3: 0x55d4c0a1b2c3 - /usr/bin/my_app+0x1b2c3`
+fn parse_frame(line: &str) -> Option<(usize, &str, usize)> {
+    let (index, rest) = line.trim_start().split_once(": 0x")?;
+    let index = index.parse().ok()?;
+    let (_, location) = rest.split_once(" - ")?;
+    let (module, offset) = location.rsplit_once("+0x")?;
+    let offset = usize::from_str_radix(offset, 16).ok()?;
+    Some((index, module, offset))
+}
+
+/// Render the symbols at `address` in the format of resolved backtraces
+fn resolve(index: usize, address: usize) -> Option<String> {
+    let mut lines = Vec::new();
+    // the address is the return address, the call is the instruction before it
+    backtrace::resolve(address.saturating_sub(1) as *mut c_void, |symbol| {
+        let name = symbol
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "<unknown>".to_owned());
+        if lines.is_empty() {
+            lines.push(format!("{:4}: {}", index, name));
+        } else {
+            lines.push(format!("      {}", name));
+        }
+        if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+            lines.push(format!("             at {}:{}", file.display(), line));
+        }
+    });
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// The path and base address of the executable or library containing `address`
+#[cfg(target_family = "unix")]
+fn module_of(address: *mut c_void) -> Option<(String, usize)> {
+    use std::{ffi::CStr, mem::MaybeUninit};
+
+    use nix::libc;
+
+    let mut info = MaybeUninit::<libc::Dl_info>::zeroed();
+    // SAFETY: dladdr only looks up the address and fills info
+    if unsafe { libc::dladdr(address as *const libc::c_void, info.as_mut_ptr()) } == 0 {
+        return None;
+    }
+    // SAFETY: dladdr succeeded so info has been filled
+    let info = unsafe { info.assume_init() };
+    if info.dli_fname.is_null() || info.dli_fbase.is_null() {
+        return None;
+    }
+    // SAFETY: dli_fname points to the nul terminated path of the loaded module
+    let path = unsafe { CStr::from_ptr(info.dli_fname) };
+    Some((path.to_string_lossy().into_owned(), info.dli_fbase as usize))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn module_of(_address: *mut c_void) -> Option<(String, usize)> {
+    None
+}